@@ -0,0 +1,24 @@
+//! Builds `binary-search-tree` with its default features off inside a `no_std` crate, so any
+//! use of `std` that slips into the library fails this build:
+//! `cargo build --manifest-path ci/no-std/Cargo.toml`
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use binary_search_tree::{AvlTree, BSTError, BSTMap, BinarySearchTree};
+
+// Touch the main trees and errors through the alloc-only API
+pub fn exercise() -> Result<Vec<u32>, BSTError<u32>> {
+    let mut tree: BinarySearchTree<u32> = [10, 5, 15, 3, 7].into_iter().collect();
+    tree.insert(12)?;
+    tree.delete(5)?;
+    tree.balance();
+    let mut avl = AvlTree::new();
+    for value in tree.iter() {
+        avl.insert(*value)?;
+    }
+    let mut map = BSTMap::new();
+    map.insert(1, "one");
+    Ok(avl.in_order_traversal().into_iter().filter(|value| map.get(&(value % 2)).is_some()).collect())
+}
@@ -0,0 +1,56 @@
+use binary_search_tree::{ArenaBST, BinarySearchTree};
+use std::time::{Duration, Instant};
+
+const VALUES: usize = 100_000;
+const ROUNDS: usize = 5;
+
+// The same pseudo-random sequence the demo uses
+fn random_values() -> Vec<u32> {
+    let mut seed: u64 = 42;
+    (0..VALUES)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as u32
+        })
+        .collect()
+}
+
+// Run `build` for every round and keep the fastest time, with the tree it built last
+fn fastest<B>(mut build: impl FnMut() -> B) -> (Duration, B) {
+    let mut best = Duration::MAX;
+    let mut built = None;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        let tree = build();
+        best = best.min(start.elapsed());
+        built = Some(tree);
+    }
+    (best, built.unwrap())
+}
+
+// Compare inserting 100k random values into the arena backend and into the boxed tree; run with
+// `cargo run --release --example arena_bench`
+fn main() {
+    let random = random_values();
+    let (boxed_time, boxed) = fastest(|| {
+        let mut tree = BinarySearchTree::new();
+        for &value in &random {
+            tree.insert_if_absent(value);
+        }
+        tree
+    });
+    let (arena_time, arena) = fastest(|| {
+        let mut tree = ArenaBST::new();
+        for &value in &random {
+            let _ = tree.insert(value);
+        }
+        tree
+    });
+    let throughput = |time: Duration| VALUES as f64 / time.as_secs_f64() / 1e6;
+    println!("Boxed: {:?} ({:.2}M inserts/s)", boxed_time, throughput(boxed_time));
+    println!("Arena: {:?} ({:.2}M inserts/s)", arena_time, throughput(arena_time));
+    println!("Arena speedup: {:.2}x", boxed_time.as_secs_f64() / arena_time.as_secs_f64());
+    let matches = arena.in_order_traversal() == boxed.in_order_traversal() && arena.pre_order_traversal() == boxed.pre_order_traversal();
+    println!("Traversals match: {}", matches); // Should print: Traversals match: true
+    assert!(matches);
+}
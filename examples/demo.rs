@@ -0,0 +1,663 @@
+use binary_search_tree::{ArenaBST, AvlTree, BSTMap, BSTMultiset, BinarySearchTree, Entry, OrderedSet, PersistentBST};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::ops::Bound;
+
+// A record ordered by its id alone, so lookups by id find the stored payload
+#[derive(Debug, Clone)]
+struct Record {
+    id: u32,
+    payload: &'static str,
+}
+
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Record {}
+
+impl PartialOrd for Record {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Record {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+// A value that can't be cloned, so the tree has to move it around
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Ticket(u32);
+
+// Insert every value, bailing out with the first error
+fn collect_unique(values: &[i32]) -> Result<BinarySearchTree<i32>, Box<dyn Error>> {
+    let mut tree = BinarySearchTree::new();
+    for &value in values {
+        tree.insert(value)?;
+    }
+    Ok(tree)
+}
+
+// Summarise any ordered set by its extremes and middle value
+fn span<S: OrderedSet<i32>>(set: &S) -> Option<(i32, i32, i32)> {
+    Some((*set.first()?, *set.nth(set.len() / 2)?, *set.last()?))
+}
+
+fn main() {
+    let mut bst = BinarySearchTree::new();
+
+    // Insert some values into the BST
+    match bst.insert(10) {
+        Ok(_) => println!("Inserted 10"),
+        Err(e) => println!("Error: {}", e),
+    }
+    match bst.insert(5) {
+        Ok(_) => println!("Inserted 5"),
+        Err(e) => println!("Error: {}", e),
+    }
+    match bst.insert(15) {
+        Ok(_) => println!("Inserted 15"),
+        Err(e) => println!("Error: {}", e),
+    }
+    match bst.insert(3) {
+        Ok(_) => println!("Inserted 3"),
+        Err(e) => println!("Error: {}", e),
+    }
+    match bst.insert(7) {
+        Ok(_) => println!("Inserted 7"),
+        Err(e) => println!("Error: {}", e),
+    }
+    match bst.insert(12) {
+        Ok(_) => println!("Inserted 12"),
+        Err(e) => println!("Error: {}", e),
+    }
+    match bst.insert(18) {
+        Ok(_) => println!("Inserted 18"),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    // Try inserting a duplicate value
+    match bst.insert(10) {
+        Ok(_) => println!("Inserted 10"),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Duplicate value
+    }
+
+    // Insert only when absent, without matching on a Result
+    println!("Inserted 12 if absent: {}", bst.insert_if_absent(12)); // Should print: false
+
+    // Bulk-load values, counting the collisions
+    let mut loaded = BinarySearchTree::new();
+    println!("Bulk load: {:?}", loaded.insert_all([1, 2, 2, 3, 3, 3])); // Should print: Bulk load: InsertReport { inserted: 3, duplicates: 3 }
+
+    // Search for values in the BST
+    println!("Search for 7: {}", bst.search(7)); // Should print: true
+    println!("Search for 12: {}", bst.search(12)); // Should print: true
+    println!("Search for 20: {}", bst.search(20)); // Should print: false
+    println!("Contains all of [5, 7, 12]: {}, any of [20, 21]: {}", bst.contains_all(&[5, 7, 12]), bst.contains_any(&[20, 21])); // Should print: Contains all of [5, 7, 12]: true, any of [20, 21]: false
+
+    // Look up borrowed String keys without moving the query
+    let words: BinarySearchTree<String> = ["pear", "apple", "fig"].iter().map(|word| word.to_string()).collect();
+    let query = String::from("fig");
+    println!("Contains {}: {}", query, words.contains(&query)); // Should print: Contains fig: true
+    let fruits: BinarySearchTree<String> = ["pear", "grape", "fig", "apple", "kiwi"].iter().map(|word| word.to_string()).collect();
+    println!("Shortest: {:?}, longest: {:?}", fruits.min_by_key(|word| word.len()), fruits.max_by_key(|word| word.len())); // Should print: Shortest: Some("fig"), longest: Some("apple")
+
+    // Get the stored record back from a query that only shares its id
+    let mut records: BinarySearchTree<Record> = [Record { id: 1, payload: "stored" }].into_iter().collect();
+    let query = Record { id: 1, payload: "query" };
+    println!("Stored payload: {:?}", records.get(&query).map(|record| record.payload)); // Should print: Some("stored")
+
+    // Replace a stored record with a new one sharing its id
+    let replaced = records.insert_or_replace(Record { id: 1, payload: "updated" });
+    println!("Replaced payload: {:?}", replaced.map(|record| record.payload)); // Should print: Some("stored")
+    println!("Updated payload: {:?}", records.get(&query).map(|record| record.payload)); // Should print: Some("updated")
+    println!("New record replaced: {:?}, len {}", records.insert_or_replace(Record { id: 2, payload: "new" }).is_some(), records.len()); // Should print: New record replaced: false, len 2
+
+    // Look a record up, inserting it only when it's missing
+    let kept = records.get_or_insert(Record { id: 2, payload: "ignored" });
+    println!("Kept payload: {}", kept.payload); // Should print: Kept payload: new
+    let created = records.get_or_insert_with(&Record { id: 3, payload: "query" }, || Record { id: 3, payload: "created" });
+    println!("Created payload: {}", created.payload); // Should print: Created payload: created
+    let again = records.get_or_insert_with(&Record { id: 3, payload: "query" }, || Record { id: 3, payload: "second" }).payload;
+    println!("Second lookup payload: {}, len {}", again, records.len()); // Should print: Second lookup payload: created, len 3
+    let mut visited: BinarySearchTree<Record> = (1..=3).map(|id| Record { id, payload: "unvisited" }).collect();
+    visited.for_each_mut(|record| record.payload = if record.id % 2 == 0 { "even" } else { "odd" });
+    let payloads: Vec<&str> = visited.iter().map(|record| record.payload).collect();
+    println!("Payloads updated in place: {:?}, valid {}", payloads, visited.is_valid_bst()); // Should print: Payloads updated in place: ["odd", "even", "odd"], valid true
+    match records.try_insert(Record { id: 3, payload: "rejected" }) {
+        Ok(record) => println!("Inserted payload: {}", record.payload),
+        Err((stored, rejected)) => println!("Try insert conflict: stored {}, rejected {}", stored.payload, rejected.payload), // Should print: Try insert conflict: stored created, rejected rejected
+    }
+    let mut tries = BinarySearchTree::new();
+    println!("Try insert 10: {:?}", tries.try_insert(10)); // Should print: Try insert 10: Ok(10)
+    println!("Try insert 10 again: {:?}", tries.try_insert(10)); // Should print: Try insert 10 again: Err((10, 10))
+
+    // Entries look a value up once, then read, update, insert or remove it
+    let updated = records.entry(Record { id: 3, payload: "unused" }).and_modify(|record| record.payload = "modified").or_insert();
+    println!("Occupied entry payload: {}", updated.payload); // Should print: Occupied entry payload: modified
+    let inserted = records.entry(Record { id: 4, payload: "fresh" }).and_modify(|record| record.payload = "modified").or_insert().payload;
+    println!("Vacant entry payload: {}, len {}", inserted, records.len()); // Should print: Vacant entry payload: fresh, len 4
+    if let Entry::Occupied(entry) = records.entry(Record { id: 1, payload: "unused" }) {
+        println!("Removed through entry: {}, len {}", entry.remove().payload, records.len()); // Should print: Removed through entry: updated, len 3
+    }
+    if let Entry::Vacant(entry) = records.entry(Record { id: 9, payload: "declined" }) {
+        println!("Vacant value handed back: {}, len {}", entry.into_value().payload, records.len()); // Should print: Vacant value handed back: declined, len 3
+    }
+
+    // Consume a tree, moving its values out in sorted order
+    let sorted_words: Vec<String> = words.into_iter().collect();
+    println!("Sorted words: {:?}", sorted_words); // Should print: ["apple", "fig", "pear"]
+
+    // Find minimum and maximum values
+    println!("Minimum value: {:?}", bst.find_min()); // Should print: Some(3)
+    println!("Maximum value: {:?}", bst.find_max()); // Should print: Some(18)
+    println!("Min and max: {:?}", bst.min_max()); // Should print: Some((3, 18))
+    println!("First and last match min and max: {}", bst.first() == bst.find_min() && bst.last() == bst.find_max()); // Should print: true
+    println!("Nth 3: {:?}", bst.nth(3)); // Should print: Some(10)
+    println!("Empty min and max: {:?}", BinarySearchTree::<i32>::new().min_max()); // Should print: None
+    let single: BinarySearchTree<i32> = [42].into_iter().collect();
+    println!("Single-value min and max: {:?}", single.min_max()); // Should print: Some((42, 42))
+
+    // Find the closest values at or around a query
+    println!("Floor of 11: {:?}, ceiling of 11: {:?}", bst.floor(&11), bst.ceiling(&11)); // Should print: Floor of 11: Some(10), ceiling of 11: Some(12)
+    println!("Floor of 2: {:?}, ceiling of 20: {:?}", bst.floor(&2), bst.ceiling(&20)); // Should print: Floor of 2: None, ceiling of 20: None
+    println!("Floor of 12: {:?}, ceiling of 12: {:?}", bst.floor(&12), bst.ceiling(&12)); // Should print: Floor of 12: Some(12), ceiling of 12: Some(12)
+    println!("Closest to 11: {:?}, to 14: {:?}, to 7: {:?}", bst.closest(&11), bst.closest(&14), bst.closest(&7)); // Should print: Closest to 11: Some(10), to 14: Some(15), to 7: Some(7)
+    println!("Closest to 100: {:?}", bst.closest_by(&100, |a: &i32, b: &i32| (a - b).abs())); // Should print: Some(18)
+
+    // Step to neighbouring values
+    println!("Successor of 7: {:?}, predecessor of 12: {:?}", bst.successor(&7), bst.predecessor(&12)); // Should print: Successor of 7: Some(10), predecessor of 12: Some(10)
+    println!("Successor of 18: {:?}, predecessor of 3: {:?}", bst.successor(&18), bst.predecessor(&3)); // Should print: Successor of 18: None, predecessor of 3: None
+    println!("Lower of 10: {:?}, higher of 10: {:?}", bst.lower(&10), bst.higher(&10)); // Should print: Lower of 10: Some(7), higher of 10: Some(12)
+    let strict_differs = |query: i32| bst.lower(&query) != bst.floor(&query) && bst.higher(&query) != bst.ceiling(&query);
+    println!("Strict neighbours differ from floor/ceiling: at 10 {}, at 11 {}", strict_differs(10), strict_differs(11)); // Should print: Strict neighbours differ from floor/ceiling: at 10 true, at 11 false
+
+    // Find where the paths to two values split
+    println!("LCA of 3 and 7: {:?}, of 3 and 12: {:?}", bst.lowest_common_ancestor(&3, &7), bst.lowest_common_ancestor(&3, &12)); // Should print: LCA of 3 and 7: Some(5), of 3 and 12: Some(10)
+    println!("LCA of 5 and 3: {:?}, of 3 and 4: {:?}", bst.lowest_common_ancestor(&5, &3), bst.lowest_common_ancestor(&3, &4)); // Should print: LCA of 5 and 3: Some(5), of 3 and 4: None
+
+    // Trace how a value is reached from the root
+    println!("Depth of 7: {:?}, path to 7: {:?}", bst.depth_of(&7), bst.path_to(&7)); // Should print: Depth of 7: Some(2), path to 7: Some([10, 5, 7])
+    println!("Depth of 10: {:?}, path to 8: {:?}", bst.depth_of(&10), bst.path_to(&8)); // Should print: Depth of 10: Some(0), path to 8: None
+
+    // Perform traversals
+    println!("In-order traversal: {:?}", bst.in_order_traversal()); // Should print: [3, 5, 7, 10, 12, 15, 18]
+    println!("Pre-order traversal: {:?}", bst.pre_order_traversal()); // Should print: [10, 5, 3, 7, 15, 12, 18]
+    println!("Post-order traversal: {:?}", bst.post_order_traversal()); // Should print: [3, 7, 5, 12, 18, 15, 10]
+    println!("In-order with depth: {:?}", bst.in_order_with_depth()); // Should print: [(3, 2), (5, 1), (7, 2), (10, 0), (12, 2), (15, 1), (18, 2)]
+    println!("Subtree heights: 5 {:?}, 3 {:?}, 4 {:?}", bst.subtree_height(&5), bst.subtree_height(&3), bst.subtree_height(&4)); // Should print: Subtree heights: 5 Some(2), 3 Some(1), 4 None
+    println!("Balance factors in {{-1, 0, 1}}: {}, max {}", bst.iter().all(|value| bst.balance_factor(value).is_some_and(|factor| factor.abs() <= 1)), bst.max_balance_factor()); // Should print: Balance factors in {-1, 0, 1}: true, max 0
+    println!("Max width: {}, widest level: {:?}, empty: {}", bst.max_width(), bst.widest_level(), BinarySearchTree::<i32>::new().max_width()); // Should print: Max width: 4, widest level: Some((2, 4)), empty: 0
+    println!("Stats: {:?}", bst.stats()); // Should print: Stats: TreeStats { node_count: 7, height: 3, leaf_count: 4, min_depth: 2, max_depth: 2, is_balanced: true }
+    println!("Profiled search: 7 {:?}, 10 {:?}, 4 {:?}", bst.search_profiled(&7), bst.search_profiled(&10), bst.search_profiled(&4)); // Should print: Profiled search: 7 (true, 3), 10 (true, 1), 4 (false, 3)
+
+    // Visit the values in order without collecting them
+    println!("Sum of values: {}", bst.fold_in_order(0, |sum, value| sum + value)); // Should print: 70
+    let mut odd = 0;
+    bst.for_each_in_order(|value| odd += value % 2);
+    println!("Odd values: {}", odd); // Should print: 4
+
+    // Walk in order with constant extra space, leaving the shape as it was
+    let shape = bst.structural_hash();
+    let mut in_place = Vec::new();
+    bst.for_each_in_order_in_place(|value| in_place.push(*value));
+    println!("In-place walk matches: {}", in_place == bst.in_order_traversal()); // Should print: true
+    let mut morris_sum = 0;
+    bst.morris_for_each(|value| morris_sum += value);
+    println!("Morris-style walk sum: {}", morris_sum); // Should print: Morris-style walk sum: 70
+    println!("Shape restored: {}", shape == bst.structural_hash() && bst.is_valid_bst() && bst.verify_caches().is_ok()); // Should print: true
+
+    // Iterate lazily, stopping early
+    println!("First three values: {:?}", bst.iter().take(3).collect::<Vec<_>>()); // Should print: [3, 5, 7]
+    println!("Three largest: {:?}", bst.iter_rev().take(3).collect::<Vec<_>>()); // Should print: [18, 15, 12]
+    let mut reversed = bst.in_order_traversal();
+    reversed.reverse();
+    println!("Reverse iterator matches: {}", bst.iter_rev().copied().collect::<Vec<_>>() == reversed); // Should print: true
+    println!("Reversed iter matches iter_rev: {}", bst.iter().rev().eq(bst.iter_rev())); // Should print: true
+    let mut both_ends = bst.iter();
+    let mut interleaved = Vec::new();
+    while let Some(low) = both_ends.next() {
+        interleaved.push(*low);
+        interleaved.extend(both_ends.next_back());
+    }
+    println!("Interleaved from both ends: {:?}", interleaved); // Should print: [3, 18, 5, 15, 7, 12, 10]
+    println!("Iterator matches in-order traversal: {}", bst.iter().copied().collect::<Vec<_>>() == bst.in_order_traversal()); // Should print: true
+
+    // Query ranges with inclusive, exclusive and half-open bounds
+    println!("Inclusive range [5, 12]: {:?}", bst.range(&5, &12)); // Should print: [5, 7, 10, 12]
+    println!("Inclusive range [4, 6]: {:?}", bst.range(&4, &6)); // Should print: [5]
+    println!("Inverted range [12, 5]: {:?}", bst.range(&12, &5)); // Should print: []
+    println!("Count in [4, 16]: {}, in [16, 4]: {}", bst.count_in_range(&4, &16), bst.count_in_range(&16, &4)); // Should print: Count in [4, 16]: 5, in [16, 4]: 0
+    println!("Exclusive range (5, 15): {:?}", bst.range_exclusive(&5, &15)); // Should print: [7, 10, 12]
+    println!("Half-open range [5, 15): {:?}", bst.range_with_bounds(Bound::Included(&5), Bound::Excluded(&15))); // Should print: [5, 7, 10, 12]
+
+    // Mirror the tree so it reads from largest to smallest
+    let mirrored = bst.inverted();
+    println!("Mirrored pre-order: {:?}", mirrored.pre_order_traversal()); // Should print: [10, 15, 18, 12, 5, 7, 3]
+    let mut descending = bst.in_order_traversal();
+    descending.reverse();
+    println!("Mirrored in-order is the reverse: {}", mirrored.in_order_traversal() == descending); // Should print: true
+
+    // Sketch the left profile of the tree
+    println!("Level representatives: {:?}", bst.level_representatives()); // Should print: [10, 5, 3]
+
+    // Group values by their tens digit
+    println!("Grouped by tens: {:?}", bst.group_by(|v| v / 10)); // Should print: [(0, [3, 5, 7]), (1, [10, 12, 15, 18])]
+
+    // Export the tree as an adjacency list
+    println!("Adjacency list: {:?}", bst.to_adjacency_list()); // Should print: [(3, []), (5, [3, 7]), (7, []), (10, [5, 15]), (12, []), (15, [12, 18]), (18, [])]
+
+    // Pretty-print the tree
+    println!("Tree:\n{}", bst); // Should print the tree sideways, e.g. "├── 5" and "│   ├── 3"
+    println!("Empty tree: {}", BinarySearchTree::<i32>::new()); // Should print: Empty tree: <empty>
+
+    // Export the tree for Graphviz
+    println!("Graphviz DOT:\n{}", bst.to_dot()); // Should print a digraph with edges such as "10" -> "5"
+
+    // Count the number of nodes
+    println!("Number of nodes: {}", bst.count_nodes()); // Should print: 7
+    println!("Length: {}, empty: {}", bst.len(), bst.is_empty()); // Should print: Length: 7, empty: false
+
+    // Find the median value
+    println!("Median: {:?}", bst.median()); // Should print: Some(10)
+
+    // Answer order statistics from the cached subtree sizes
+    println!("Select 0: {:?}, select 3: {:?}, select 7: {:?}", bst.select(0), bst.select(3), bst.select(7)); // Should print: Select 0: Some(3), select 3: Some(10), select 7: None
+    println!("Rank of 10: {}, rank of 11: {}", bst.rank(&10), bst.rank(&11)); // Should print: Rank of 10: 3, rank of 11: 4
+    println!("Values below 11: {}, below 0: {}, below 100: {}", bst.partition_count(|value| *value >= 11), bst.partition_count(|value| *value >= 0), bst.partition_count(|value| *value >= 100)); // Should print: Values below 11: 4, below 0: 0, below 100: 7
+    println!("1st smallest: {:?}, 1st largest: {:?}", bst.kth_smallest(1), bst.kth_largest(1)); // Should print: 1st smallest: Some(3), 1st largest: Some(18)
+    println!("3rd largest: {:?}, 0th smallest: {:?}, 8th largest: {:?}", bst.kth_largest(3), bst.kth_smallest(0), bst.kth_largest(8)); // Should print: 3rd largest: Some(12), 0th smallest: None, 8th largest: None
+
+    // Count nodes by how many children they have
+    println!("Full nodes: {}", bst.count_full_nodes()); // Should print: 3
+    println!("Half nodes: {}", bst.count_half_nodes()); // Should print: 0
+    println!("Leaves: {}, internal nodes: {}", bst.count_leaves(), bst.count_internal()); // Should print: Leaves: 4, internal nodes: 3
+
+    // Check if the tree is balanced
+    println!("Is balanced: {}", bst.is_balanced()); // Should print: true
+    let lopsided: BinarySearchTree<i32> = [10, 5, 15, 3, 12, 18, 20, 2, 1].into_iter().collect();
+    println!("Lopsided below the root balanced: {}", lopsided.is_balanced()); // Should print: false
+
+    // Measure skew through the shallowest leaf and the deepest node
+    println!("Shallowest leaf: {:?}", bst.shallowest_leaf()); // Should print: Some((3, 2))
+    println!("Deepest node: {:?}", bst.deepest_node()); // Should print: Some((3, 2))
+
+    // Build a tree from an iterator, skipping duplicates
+    let mut collected: BinarySearchTree<i32> = vec![10, 5, 15, 5, 10].into_iter().collect();
+    collected.extend([15, 20, 1]);
+    println!("Collected: {:?}", collected.in_order_traversal()); // Should print: [1, 5, 10, 15, 20]
+    collected.clear();
+    println!("Cleared: empty {}, {:?}", collected.is_empty(), collected.in_order_traversal()); // Should print: Cleared: empty true, []
+
+    // Rebalance a degenerate tree built from sorted input
+    let mut sorted: BinarySearchTree<i32> = (1..=15).collect();
+    println!("Height before balancing: {}", sorted.height()); // Should print: 15
+    sorted.balance();
+    println!("Height after balancing: {}", sorted.height()); // Should print: 4
+    println!("Balanced in-order traversal: {:?}", sorted.in_order_traversal()); // Should print: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]
+
+    // Merge a sorted batch into an existing tree, rebuilding it balanced
+    let mut batched: BinarySearchTree<i32> = [10, 5, 15, 3, 7, 12, 18].into_iter().collect();
+    batched.insert_sorted(&[1, 2, 20, 25, 30]).unwrap();
+    println!("After sorted batch: {:?}", batched.in_order_traversal()); // Should print: [1, 2, 3, 5, 7, 10, 12, 15, 18, 20, 25, 30]
+    println!("Batch balanced: {}, height {}", batched.is_balanced(), batched.height()); // Should print: Batch balanced: true, height 4
+    match batched.insert_sorted(&[4, 12]) {
+        Ok(_) => println!("Inserted the batch"),
+        Err(e) => println!("Error: {}, len {}", e, batched.len()), // Should print: Error: Duplicate value: cannot insert 12 twice, len 12
+    }
+
+    // Build a balanced tree directly from sorted data
+    let built = BinarySearchTree::from_sorted_slice(&[1, 2, 3, 4, 5, 6, 7]);
+    println!("Built height: {}, balanced: {}", built.height(), built.is_balanced()); // Should print: Built height: 3, balanced: true
+    let unsorted = BinarySearchTree::from_sorted_slice(&[3, 1, 2]);
+    println!("Search for 3 in a tree built from unsorted input: {}", unsorted.search(3)); // Should print: false
+    println!("Valid BST from unsorted input: {}, from sorted input: {}", unsorted.is_valid_bst(), built.is_valid_bst()); // Should print: Valid BST from unsorted input: false, from sorted input: true
+
+    // Round-trip a shuffled vector with duplicates through a tree and back
+    let converted = BinarySearchTree::from(vec![8, 3, 5, 1, 8, 9, 3, 2, 7, 5]);
+    println!("From Vec: height {}, balanced {}", converted.height(), converted.is_balanced()); // Should print: From Vec: height 3, balanced true
+    println!("Into sorted Vec: {:?}", converted.into_sorted_vec()); // Should print: Into sorted Vec: [1, 2, 3, 5, 7, 8, 9]
+    let standard: BTreeSet<i32> = [8, 3, 5, 1, 9].into_iter().collect();
+    let from_standard = BinarySearchTree::from(standard.clone());
+    println!("From BTreeSet: {:?}, balanced {}", from_standard.in_order_traversal(), from_standard.is_balanced()); // Should print: From BTreeSet: [1, 3, 5, 8, 9], balanced true
+    println!("Round trip through BTreeSet preserved: {}", BTreeSet::from(from_standard) == standard); // Should print: true
+    let ranked: BinarySearchTree<i32> = [10, 5, 15, 3, 7, 12, 18].into_iter().collect();
+    println!("Top 3: {:?}, top 0: {:?}, top 10: {:?}", ranked.clone().into_top_k(3), ranked.clone().into_top_k(0), ranked.into_top_k(10)); // Should print: Top 3: [18, 15, 12], top 0: [], top 10: [18, 15, 12, 10, 7, 5, 3]
+
+    // Compare structural hashes against trees holding the same values
+    let mut same_shape = BinarySearchTree::new();
+    for value in [10, 5, 15, 3, 7, 12, 18] {
+        same_shape.insert(value).unwrap();
+    }
+    let mut skewed = BinarySearchTree::new();
+    for value in [3, 5, 7, 10, 12, 15, 18] {
+        skewed.insert(value).unwrap();
+    }
+    println!("Same shape hashes equal: {}", bst.structural_hash() == same_shape.structural_hash()); // Should print: true
+    println!("Skewed shape hashes equal: {}", bst.structural_hash() == skewed.structural_hash()); // Should print: false
+    println!("Structurally equal: same shape {}, skewed {}, equal contents {}", bst.structural_eq(&same_shape), bst.structural_eq(&skewed), bst == skewed); // Should print: Structurally equal: same shape true, skewed false, equal contents true
+    let mut lopsided = same_shape.clone();
+    lopsided.insert(1).unwrap();
+    println!("Symmetric: perfect {}, lopsided {}, skewed {}", same_shape.is_symmetric(), lopsided.is_symmetric(), skewed.is_symmetric()); // Should print: Symmetric: perfect true, lopsided false, skewed false
+    let inward: BinarySearchTree<i32> = [10, 5, 15, 7, 12].into_iter().collect();
+    println!("Symmetric with inner children only: {}", inward.is_symmetric()); // Should print: true
+
+    // Measure the longest path between two nodes
+    println!("Diameter: {}, skewed diameter: {}", bst.diameter(), skewed.diameter()); // Should print: Diameter: 4, skewed diameter: 6
+    println!("Skewed leaves: {}, internal nodes: {}", skewed.count_leaves(), skewed.count_internal()); // Should print: Skewed leaves: 1, internal nodes: 6
+
+    // Truncate a copy of the sample tree below depth 1
+    println!("Truncated values: {:?}", same_shape.truncate_to_height(1)); // Should print: [3, 7, 12, 18]
+    println!("In-order traversal after truncating: {:?}", same_shape.in_order_traversal()); // Should print: [5, 10, 15]
+
+    // Diff two trees by contents
+    let mut base = BinarySearchTree::new();
+    for value in [1, 2, 3] {
+        base.insert(value).unwrap();
+    }
+    let mut target = BinarySearchTree::new();
+    for value in [2, 3, 4] {
+        target.insert(value).unwrap();
+    }
+    let (added, removed) = base.content_diff(&target);
+    println!("Diff [1, 2, 3] -> [2, 3, 4]: {:?}", (&added, &removed)); // Should print: ([4], [1])
+
+    // Replay the diff onto the base tree
+    match base.apply_diff(&added, &removed) {
+        Ok(_) => println!("Applied diff: {:?}", base.in_order_traversal()), // Should print: Applied diff: [2, 3, 4]
+        Err(e) => println!("Error: {}", e),
+    }
+    println!("Base matches target: {}", base.in_order_traversal() == target.in_order_traversal()); // Should print: true
+
+    // Replaying the same diff again no longer matches the base and is rolled back
+    match base.apply_diff(&added, &removed) {
+        Ok(_) => println!("Applied diff: {:?}", base.in_order_traversal()),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Duplicate value
+    }
+    println!("Base after rollback: {:?}", base.in_order_traversal()); // Should print: [2, 3, 4]
+
+    // Check whether key ranges overlap
+    let mut high = BinarySearchTree::new();
+    for value in [20, 25, 30] {
+        high.insert(value).unwrap();
+    }
+    println!("Base and target ranges disjoint: {}", base.ranges_disjoint(&target)); // Should print: false
+    println!("Base and high ranges disjoint: {}", base.ranges_disjoint(&high)); // Should print: true
+
+    // Concatenate trees with disjoint ranges
+    match base.concat(high) {
+        Ok(_) => println!("Concatenated: {:?}", base.in_order_traversal()), // Should print: Concatenated: [2, 3, 4, 20, 25, 30]
+        Err(e) => println!("Error: {}", e),
+    }
+    match base.concat(target) {
+        Ok(_) => println!("Concatenated: {:?}", base.in_order_traversal()),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Overlapping ranges
+    }
+
+    // Swap out the subtree rooted at 15 in the skewed tree
+    let mut branch = BinarySearchTree::new();
+    for value in [16, 14, 17] {
+        branch.insert(value).unwrap();
+    }
+    match skewed.replace_subtree(&15, branch) {
+        Ok(old) => println!("Replaced subtree {:?}: {:?}", old.in_order_traversal(), skewed.in_order_traversal()), // Should print: Replaced subtree [15, 18]: [3, 5, 7, 10, 12, 14, 16, 17]
+        Err(e) => println!("Error: {}", e),
+    }
+    let mut misfit = BinarySearchTree::new();
+    misfit.insert(1).unwrap();
+    match skewed.replace_subtree(&16, misfit) {
+        Ok(old) => println!("Replaced subtree {:?}: {:?}", old.in_order_traversal(), skewed.in_order_traversal()),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Out of range
+    }
+
+    // Delete a node
+    match bst.delete(15) {
+        Ok(_) => println!("Deleted 15"),
+        Err(e) => println!("Error: {}", e),
+    }
+    println!("In-order traversal after deleting 15: {:?}", bst.in_order_traversal()); // Should print: [3, 5, 7, 10, 12, 18]
+
+    // Try deleting a non-existent value
+    match bst.delete(20) {
+        Ok(_) => println!("Deleted 20"),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Value not found
+    }
+
+    println!("Length after deletions: {}", bst.len()); // Should print: 6
+    println!("Sizes agree after deletions: {}", bst.len() == bst.count_nodes()); // Should print: true
+    println!("Select 4 after deletions: {:?}, rank of 18: {}", bst.select(4), bst.rank(&18)); // Should print: Select 4 after deletions: Some(12), rank of 18: 5
+
+    // Check height of the tree, read from the cached heights, and that every cache is fresh
+    println!("Height of the tree: {}", bst.height()); // Should print: 3
+    println!("Caches after deletions: {:?}", bst.verify_caches()); // Should print: Ok(())
+
+    // Move a value to a new place in the order
+    let mut relocated: BinarySearchTree<i32> = [10, 5, 15, 3, 7, 12, 18].into_iter().collect();
+    relocated.replace_value(&5, 6).unwrap();
+    println!("Replaced 5 with 6: {:?}, contains 5 {}, valid {}", relocated.in_order_traversal(), relocated.contains(&5), relocated.is_valid_bst()); // Should print: Replaced 5 with 6: [3, 6, 7, 10, 12, 15, 18], contains 5 false, valid true
+    match relocated.replace_value(&6, 12) {
+        Ok(_) => println!("Replaced 6 with 12"),
+        Err(e) => println!("Error: {}, len {}", e, relocated.len()), // Should print: Error: Duplicate value: cannot insert 12 twice, len 7
+    }
+
+    // Use the tree as an ordered key-value map
+    let mut ages = BSTMap::new();
+    ages.insert("carol", 41);
+    ages.insert("alice", 30);
+    println!("Previous value for alice: {:?}", ages.insert("alice", 31)); // Should print: Some(30)
+    if let Some(age) = ages.get_mut(&"carol") {
+        *age += 1;
+    }
+    println!("Map entries: {:?}", ages.iter().collect::<Vec<_>>()); // Should print: [("alice", 31), ("carol", 42)]
+    println!("Removed bob: {:?}", ages.remove(&"bob")); // Should print: None
+
+    // Count repeated values with a multiset
+    let mut multiset = BSTMultiset::new();
+    for value in [5, 3, 5, 5] {
+        multiset.insert(value);
+    }
+    println!("Count of 5: {}", multiset.count_of(&5)); // Should print: 3
+    multiset.remove_one(&3);
+    println!("Multiset traversal: {:?}", multiset.in_order_traversal()); // Should print: [5, 5, 5]
+
+    let mut tickets: BinarySearchTree<Ticket> = [10, 5, 15, 3, 7, 12, 18].into_iter().map(Ticket).collect();
+    tickets.delete(Ticket(10)).unwrap();
+    tickets.delete(Ticket(3)).unwrap();
+    let remaining: Vec<u32> = tickets.iter().map(|ticket| ticket.0).collect();
+    println!("Tickets after deleting 10 and 3: {:?}", remaining); // Should print: [5, 7, 12, 15, 18]
+    println!("Removed ticket: {:?}", tickets.remove(&Ticket(15))); // Should print: Some(Ticket(15))
+    println!("Removed again: {:?}", tickets.remove(&Ticket(15))); // Should print: None
+    match tickets.delete(Ticket(15)) {
+        Ok(_) => println!("Deleted 15 twice"),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Value not found: Ticket(15) isn't in the tree
+    }
+
+    // Errors carry the offending value and box into `Box<dyn Error>` for `?`
+    match collect_unique(&[4, 8, 15, 8]) {
+        Ok(tree) => println!("Collected {} values", tree.len()),
+        Err(e) => println!("Boxed error: {}", e), // Should print: Boxed error: Duplicate value: cannot insert 8 twice
+    }
+
+    let mut queue: BinarySearchTree<i32> = [10, 5, 15, 3, 7, 12, 18].into_iter().collect();
+    println!("Popped max: {:?}", queue.pop_max()); // Should print: Some(18)
+    queue.insert(18).unwrap();
+    let mut drained = Vec::new();
+    println!("Next to pop: {:?} and {:?}", queue.peek_min(), queue.peek_max()); // Should print: Next to pop: Some(3) and Some(18)
+    while let Some(value) = queue.pop_min() {
+        drained.push(value);
+    }
+    println!("Drained by pop_min: {:?}", drained); // Should print: [3, 5, 7, 10, 12, 15, 18]
+    println!("Popped from empty: {:?}, len {}", queue.pop_min(), queue.len()); // Should print: Popped from empty: None, len 0
+
+    let forward: BinarySearchTree<i32> = [10, 5, 15].into_iter().collect();
+    let backward: BinarySearchTree<i32> = [15, 10, 5].into_iter().collect();
+    println!("Same contents, different shape: equal {}", forward == backward); // Should print: Same contents, different shape: equal true
+    println!("Shapes match: {}", forward.structural_hash() == backward.structural_hash()); // Should print: Shapes match: false
+    println!("Equal to the sample tree: {}", forward == bst); // Should print: Equal to the sample tree: false
+
+    let original: BinarySearchTree<i32> = [10, 5, 15, 3, 7, 12, 18].into_iter().collect();
+    let mut snapshot = original.clone();
+    snapshot.delete(10).unwrap();
+    snapshot.insert(20).unwrap();
+    println!("Clone after edits: {:?}", snapshot.in_order_traversal()); // Should print: [3, 5, 7, 12, 15, 18, 20]
+    println!("Original untouched: {:?}", original.in_order_traversal()); // Should print: [3, 5, 7, 10, 12, 15, 18]
+    let mut evens = original.clone();
+    evens.retain(|value| value % 2 == 0);
+    println!("Retained evens: {:?}, caches: {:?}", evens.in_order_traversal(), evens.verify_caches()); // Should print: Retained evens: [10, 12, 18], caches: Ok(())
+    let mut window = original.clone();
+    window.prune_below(&10);
+    println!("Pruned below 10: {:?}, len {}, caches {:?}", window.in_order_traversal(), window.len(), window.verify_caches()); // Should print: Pruned below 10: [10, 12, 15, 18], len 4, caches Ok(())
+    window.prune_above(&14);
+    println!("Pruned above 14: {:?}, len {}, valid {}", window.in_order_traversal(), window.len(), window.is_valid_bst()); // Should print: Pruned above 14: [10, 12], len 2, valid true
+    let mut trimmed = original.clone();
+    println!("Drained 5..=12: {:?}, left {:?}, len {}, caches {:?}", trimmed.drain_range(&5, &12), trimmed.in_order_traversal(), trimmed.len(), trimmed.verify_caches()); // Should print: Drained 5..=12: [5, 7, 10, 12], left [3, 15, 18], len 3, caches Ok(())
+    println!("Drained 8..=9: {:?}, drained 12..=5: {:?}, len {}", trimmed.drain_range(&8, &9), trimmed.drain_range(&12, &5), trimmed.len()); // Should print: Drained 8..=9: [], drained 12..=5: [], len 3
+    println!("Drained everything: {:?}, empty {}", trimmed.drain_range(&0, &100), trimmed.is_empty()); // Should print: Drained everything: [3, 15, 18], empty true
+    let remainders = original.map(|value| value % 10);
+    println!("Mapped to remainders: {:?}, valid: {}", remainders.in_order_traversal(), remainders.is_valid_bst()); // Should print: Mapped to remainders: [0, 2, 3, 5, 7, 8], valid: true
+    let mut combined = original.clone();
+    let mut incoming: BinarySearchTree<i32> = [4, 6, 20, 10].into_iter().collect();
+    println!("Appended: {:?}", combined.append(&mut incoming)); // Should print: Appended: InsertReport { inserted: 3, duplicates: 1 }
+    println!("After append: {:?}, other empty: {}, balanced: {}", combined.in_order_traversal(), incoming.is_empty(), combined.is_balanced()); // Should print: After append: [3, 4, 5, 6, 7, 10, 12, 15, 18, 20], other empty: true, balanced: true
+    let (below, above) = original.clone().split(&10);
+    println!("Split at 10: {:?} and {:?}", below.in_order_traversal(), above.in_order_traversal()); // Should print: Split at 10: [3, 5, 7] and [10, 12, 15, 18]
+    let merged = above.merge(below).unwrap();
+    println!("Merged back: {:?}, equal to original: {}", merged.in_order_traversal(), merged == original); // Should print: Merged back: [3, 5, 7, 10, 12, 15, 18], equal to original: true
+    match merged.merge(evens) {
+        Ok(_) => println!("Merged overlapping trees"),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Overlapping ranges: trees must not interleave
+    }
+
+    // Combine two overlapping trees as sets
+    let left_set: BinarySearchTree<i32> = [10, 5, 15, 3, 7].into_iter().collect();
+    let right_set: BinarySearchTree<i32> = [7, 10, 12, 18, 20].into_iter().collect();
+    println!("Union: {:?}", left_set.union(&right_set).in_order_traversal()); // Should print: [3, 5, 7, 10, 12, 15, 18, 20]
+    println!("Intersection: {:?}", left_set.intersection(&right_set).in_order_traversal()); // Should print: [7, 10]
+    let pair: BinarySearchTree<i32> = [3, 7].into_iter().collect();
+    let empty = BinarySearchTree::<i32>::new();
+    println!("{{3, 7}} subset of left: {}, left subset of {{3, 7}}: {}", pair.is_subset(&left_set), left_set.is_subset(&pair)); // Should print: {3, 7} subset of left: true, left subset of {3, 7}: false
+    println!("Disjoint from right: {}, from {{2, 4}}: {}", left_set.is_disjoint(&right_set), left_set.is_disjoint(&[2, 4].into_iter().collect())); // Should print: Disjoint from right: false, from {2, 4}: true
+    println!("Empty: subset {}, disjoint {}", empty.is_subset(&pair), empty.is_disjoint(&pair)); // Should print: Empty: subset true, disjoint true
+    println!("Values in 13..=14: {}, in 14..=15: {}", left_set.intersects_range(&13, &14), left_set.intersects_range(&14, &15)); // Should print: Values in 13..=14: false, in 14..=15: true
+    println!("Range bounds of 6..=13: {:?}, of 8..=9: {:?}, of 7..=7: {:?}", bst.range_bounds(&6, &13), bst.range_bounds(&8, &9), bst.range_bounds(&7, &7)); // Should print: Range bounds of 6..=13: Some((7, 12)), of 8..=9: None, of 7..=7: Some((7, 7))
+    println!("Difference: {:?}", left_set.difference(&right_set).in_order_traversal()); // Should print: [3, 5, 15]
+    println!("Union balanced: {}", left_set.union(&right_set).is_balanced()); // Should print: true
+    println!("Symmetric difference: {:?}", left_set.symmetric_difference(&right_set).in_order_traversal()); // Should print: [3, 5, 12, 15, 18, 20]
+    let pairs = [(&left_set, &right_set), (&right_set, &left_set), (&left_set, &pair), (&pair, &empty), (&left_set, &left_set)];
+    let agree = pairs.iter().all(|(a, b)| a.symmetric_difference(b) == a.union(b).difference(&a.intersection(b)));
+    println!("Symmetric difference is union minus intersection: {}", agree); // Should print: true
+    let ops = left_set.set_ops(&right_set);
+    println!("Set ops agree: {}", ops.union == left_set.union(&right_set) && ops.intersection == left_set.intersection(&right_set) && ops.difference == left_set.difference(&right_set) && ops.symmetric_difference == left_set.symmetric_difference(&right_set)); // Should print: true
+    println!("Clone keeps the shape: {}", original.clone().structural_hash() == original.structural_hash()); // Should print: true
+
+    // Count the comparisons made by a sorted build against a balanced one over the same 1023 values
+    let (mut sorted_cost, mut balanced_cost) = (0, 0);
+    let mut degenerate = BinarySearchTree::new();
+    for value in 0..1023 {
+        degenerate.insert_counting(value, &mut sorted_cost).unwrap();
+    }
+    let mut level_built = BinarySearchTree::new();
+    for value in BinarySearchTree::from_sorted_slice(&(0..1023).collect::<Vec<_>>()).pre_order_traversal() {
+        level_built.insert_counting(value, &mut balanced_cost).unwrap();
+    }
+    println!("Build comparisons: sorted {} (n^2/2 = {}), balanced {} (n log2 n = {})", sorted_cost, 1023 * 1023 / 2, balanced_cost, 1023 * 10); // Should print: Build comparisons: sorted 522753 (n^2/2 = 523264), balanced 8194 (n log2 n = 10230)
+
+    // Append 300k values one tree at a time; each concat rebalances, so the result stays shallow
+    let mut joined = BinarySearchTree::new();
+    for value in 0..300_000 {
+        joined.concat([value].into_iter().collect()).unwrap();
+    }
+    println!("Joined length: {}, height: {}, balanced: {}", joined.len(), joined.height(), joined.is_balanced()); // Should print: Joined length: 300000, height: 19, balanced: true
+    drop(joined);
+
+    // Keep a tree balanced automatically, even for ascending input
+    let mut avl: AvlTree<i32> = (1..=1000).collect();
+    let bound = 1.44 * 1000f64.log2();
+    println!("AVL height: {}, within 1.44 log2(n): {}", avl.height(), (avl.height() as f64) <= bound); // Should print: AVL height: 10, within 1.44 log2(n): true
+    for value in 1..=500 {
+        avl.delete(value).unwrap();
+    }
+    println!("AVL after deletions: len {}, height {}, balanced {}", avl.len(), avl.height(), avl.is_balanced()); // Should print: AVL after deletions: len 500, height 10, balanced true
+    println!("AVL min: {:?}, caches: {:?}", avl.find_min(), avl.verify_caches()); // Should print: AVL min: Some(501), caches: Ok(())
+    let plain: BinarySearchTree<i32> = (501..=1000).collect();
+    println!("Spans agree: {:?} {}", span(&avl), span(&avl) == span(&plain)); // Should print: Spans agree: Some((501, 751, 1000)) true
+
+    // Keep nodes in one arena instead of boxing each one; the same 100k pseudo-random values
+    // give the same traversals as the boxed tree
+    let mut seed: u64 = 42;
+    let random: Vec<u32> = (0..100_000)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as u32
+        })
+        .collect();
+    let mut arena: ArenaBST<u32> = random.iter().copied().collect();
+    let boxed: BinarySearchTree<u32> = random.iter().copied().collect();
+    println!("Arena matches boxed: {}", arena.in_order_traversal() == boxed.in_order_traversal() && arena.pre_order_traversal() == boxed.pre_order_traversal()); // Should print: true
+    let slots = arena.slots();
+    for value in random.iter().step_by(2) {
+        arena.remove(value);
+    }
+    arena.extend(random.iter().step_by(2).copied());
+    println!("Freed slots reused: {}, len {}", arena.slots() == slots, arena.len() == boxed.len()); // Should print: Freed slots reused: true, len true
+
+    // Share one read-only tree between threads without cloning its nodes
+    let shared = boxed.into_shared();
+    let handles: Vec<_> = (0..4)
+        .map(|thread| {
+            let shared = shared.clone();
+            let queries: Vec<u32> = random.iter().skip(thread).step_by(4).copied().collect();
+            std::thread::spawn(move || queries.iter().filter(|value| shared.contains(value)).count())
+        })
+        .collect();
+    let found: usize = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+    println!("Found across threads: {}, shared len {}", found == random.len(), shared.len() == arena.len()); // Should print: Found across threads: true, shared len true
+
+    // Keep every version of a persistent tree; each update copies only the path it changes
+    let version1: PersistentBST<u32> = (0..1023).map(|i| i * 613 % 1023).collect();
+    let version2 = version1.insert(2000);
+    let version3 = version2.delete(&511);
+    println!("Old version unchanged: {}, len {}", version1.iter().copied().eq(0..1023), version1.len()); // Should print: Old version unchanged: true, len 1023
+    println!("New versions: has 2000 {}, has 511 {}", version2.contains(&2000), version3.contains(&511)); // Should print: New versions: has 2000 true, has 511 false
+    println!("Nodes copied by the insert: {} of {}", version2.len() - version2.shared_nodes(&version1), version2.len()); // Should print: Nodes copied by the insert: 6 of 1024
+    println!("Nodes copied by the delete: {} of {}", version3.len() - version3.shared_nodes(&version2), version3.len()); // Should print: Nodes copied by the delete: 31 of 1023
+
+    // Rebuild after deleting 90% of the values, reclaiming the slack
+    let mut thinned: BinarySearchTree<u32> = (0..10_000).map(|i| i * 7919 % 10_000).collect();
+    let mut thinned_arena: ArenaBST<u32> = (0..10_000).map(|i| i * 7919 % 10_000).collect();
+    for value in (0..10_000).filter(|value| value % 10 != 0) {
+        thinned.delete(value).unwrap();
+        thinned_arena.delete(value).unwrap();
+    }
+    thinned.rebuild();
+    thinned_arena.rebuild();
+    println!("Rebuilt: balanced {}, height {}, len {}", thinned.is_balanced(), thinned.height(), thinned.len()); // Should print: Rebuilt: balanced true, height 10, len 1000
+    println!("Rebuilt arena: height {}, slots {}, matches {}", thinned_arena.height(), thinned_arena.slots(), thinned_arena.in_order_traversal() == thinned.in_order_traversal()); // Should print: Rebuilt arena: height 10, slots 1000, matches true
+
+    // Size the arena up front for a bulk load
+    let mut presized: ArenaBST<u32> = ArenaBST::with_capacity(10_000);
+    let capacity = presized.capacity();
+    presized.extend((0..10_000).map(|i| i * 7919 % 10_000));
+    println!("Presized arena: capacity {}, unchanged after 10k inserts: {}", capacity, presized.capacity() == capacity); // Should print: Presized arena: capacity 10000, unchanged after 10k inserts: true
+    thinned_arena.reserve(5_000);
+    thinned.reserve(5_000);
+    println!("Reserved: at least 6000 slots {}", thinned_arena.capacity() >= 6_000); // Should print: Reserved: at least 6000 slots true
+
+    // Order values by a derived key with a custom comparator
+    let mut by_length = BinarySearchTree::with_comparator(|a: &&str, b: &&str| a.len().cmp(&b.len()));
+    for word in ["banana", "fig", "apple", "kiwi"] {
+        by_length.insert(word).unwrap();
+    }
+    println!("Words by length: {:?}", by_length.in_order_traversal()); // Should print: ["fig", "kiwi", "apple", "banana"]
+    println!("Same length as pear: {:?}", by_length.get(&"pear")); // Should print: Some("kiwi")
+    match by_length.insert("plum") {
+        Ok(_) => println!("Inserted plum"),
+        Err(e) => println!("Error: {}", e), // Should print: Error: Duplicate value: cannot insert "plum" twice
+    }
+}
@@ -0,0 +1,370 @@
+use crate::BSTError;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+// A node stored in the arena, linking to its children by index
+#[derive(Debug, Clone)]
+struct ArenaNode<T> {
+    value: T,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// A binary search tree that keeps all of its nodes in one `Vec` and links them by index instead
+/// of boxing each one, so nodes sit together in memory. Slots freed by deletes are reused by
+/// later inserts.
+#[derive(Debug, Clone)]
+pub struct ArenaBST<T: Ord> {
+    nodes: Vec<Option<ArenaNode<T>>>,
+    free: Vec<usize>,
+    root: Option<usize>,
+}
+
+// Implement methods for the arena-backed tree
+impl<T: Ord> ArenaBST<T> {
+    /// Create a new empty arena tree
+    pub fn new() -> Self {
+        ArenaBST {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// Create a new empty arena tree with room for `capacity` nodes, so inserting that many values
+    /// doesn't reallocate the arena
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArenaBST {
+            nodes: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// Make room for at least `additional` more values without reallocating, counting the free
+    /// slots left by deletes towards it
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional.saturating_sub(self.free.len()));
+    }
+
+    /// Number of slots the arena can hold before it has to reallocate
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    // The node in a live slot
+    fn node(&self, index: usize) -> &ArenaNode<T> {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    // The node in a live slot, mutably
+    fn node_mut(&mut self, index: usize) -> &mut ArenaNode<T> {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    // Find the slot holding `value` along with its parent and the side it hangs from, or the
+    // parent and side where `value` would be linked in if it's missing
+    fn locate(&self, value: &T) -> (Option<(usize, Ordering)>, Option<usize>) {
+        let mut parent = None;
+        let mut current = self.root;
+        while let Some(index) = current {
+            let node = self.node(index);
+            let ordering = value.cmp(&node.value);
+            current = match ordering {
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+                Ordering::Equal => break,
+            };
+            parent = Some((index, ordering));
+        }
+        (parent, current)
+    }
+
+    // Point the link below `parent` on the given side, or the root if there is no parent, at `child`
+    fn set_link(&mut self, parent: Option<(usize, Ordering)>, child: Option<usize>) {
+        match parent {
+            None => self.root = child,
+            Some((index, Ordering::Less)) => self.node_mut(index).left = child,
+            Some((index, _)) => self.node_mut(index).right = child,
+        }
+    }
+
+    // Empty a slot, putting it on the free list, and hand back its value
+    fn release(&mut self, index: usize) -> T {
+        self.free.push(index);
+        self.nodes[index].take().unwrap().value
+    }
+
+    /// Insert a value into the tree, reusing a freed slot if there is one
+    pub fn insert(&mut self, value: T) -> Result<(), BSTError<T>> {
+        let (parent, found) = self.locate(&value);
+        if found.is_some() {
+            return Err(BSTError::DuplicateValue(value));
+        }
+        let node = Some(ArenaNode {
+            value,
+            left: None,
+            right: None,
+        });
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        self.set_link(parent, Some(index));
+        Ok(())
+    }
+
+    /// Search for a value in the tree
+    pub fn search(&self, value: T) -> bool {
+        self.contains(&value)
+    }
+
+    /// Check whether a value is in the tree without taking ownership of the query
+    pub fn contains(&self, value: &T) -> bool {
+        self.locate(value).1.is_some()
+    }
+
+    /// Get a reference to the stored value equal to `value`
+    pub fn get(&self, value: &T) -> Option<&T> {
+        self.locate(value).1.map(|index| &self.node(index).value)
+    }
+
+    /// Delete a value from the tree, handing it back in `ValueNotFound` if it isn't there
+    pub fn delete(&mut self, value: T) -> Result<(), BSTError<T>> {
+        match self.remove(&value) {
+            Some(_) => Ok(()),
+            None => Err(BSTError::ValueNotFound(value)),
+        }
+    }
+
+    /// Remove the value equal to `value` and return the stored value, or `None` if there is none.
+    /// Its slot goes on the free list.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let (parent, index) = self.locate(value);
+        let index = index?;
+        let node = self.node(index);
+        match (node.left, node.right) {
+            (None, child) | (child, None) => {
+                self.set_link(parent, child);
+                Some(self.release(index))
+            }
+            (Some(_), Some(right)) => {
+                // Node has two children, move the in-order successor's value (minimum in the right subtree) into it
+                let mut successor_parent = (index, Ordering::Greater);
+                let mut successor = right;
+                while let Some(left) = self.node(successor).left {
+                    successor_parent = (successor, Ordering::Less);
+                    successor = left;
+                }
+                let successor_right = self.node(successor).right;
+                self.set_link(Some(successor_parent), successor_right);
+                let successor_value = self.release(successor);
+                Some(core::mem::replace(&mut self.node_mut(index).value, successor_value))
+            }
+        }
+    }
+
+    /// Find the minimum value in the tree
+    pub fn find_min(&self) -> Option<&T> {
+        let mut index = self.root?;
+        while let Some(left) = self.node(index).left {
+            index = left;
+        }
+        Some(&self.node(index).value)
+    }
+
+    /// Find the maximum value in the tree
+    pub fn find_max(&self) -> Option<&T> {
+        let mut index = self.root?;
+        while let Some(right) = self.node(index).right {
+            index = right;
+        }
+        Some(&self.node(index).value)
+    }
+
+    /// Iterate over the values in sorted order
+    pub fn iter(&self) -> ArenaIter<'_, T> {
+        let mut iter = ArenaIter {
+            arena: self,
+            stack: Vec::new(),
+        };
+        iter.push_left_spine(self.root);
+        iter
+    }
+
+    /// In-order traversal
+    pub fn in_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+
+    /// Pre-order traversal
+    pub fn pre_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = self.node(index);
+            result.push(node.value.clone());
+            stack.extend(node.right);
+            stack.extend(node.left);
+        }
+        result
+    }
+
+    /// Post-order traversal
+    pub fn post_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        // Root, right, left is exactly the reverse of post-order
+        let mut result = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = self.node(index);
+            result.push(node.value.clone());
+            stack.extend(node.left);
+            stack.extend(node.right);
+        }
+        result.reverse();
+        result
+    }
+
+    /// Number of values in the tree, in O(1)
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    /// Check if the tree holds no values, in O(1)
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Height of the tree in nodes (0 when empty), walking it level by level
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut level: Vec<usize> = self.root.into_iter().collect();
+        while !level.is_empty() {
+            height += 1;
+            level = level
+                .iter()
+                .flat_map(|&index| {
+                    let node = self.node(index);
+                    node.left.into_iter().chain(node.right)
+                })
+                .collect();
+        }
+        height
+    }
+
+    /// Rebuild the tree balanced, with minimal height, and compact the arena: the values move into a
+    /// fresh `Vec` in sorted order with no free slots, in O(n)
+    pub fn rebuild(&mut self) {
+        let mut order = Vec::with_capacity(self.len());
+        let mut stack = Vec::new();
+        let mut current = self.root;
+        while current.is_some() || !stack.is_empty() {
+            while let Some(index) = current {
+                stack.push(index);
+                current = self.node(index).left;
+            }
+            let index = stack.pop().unwrap();
+            order.push(index);
+            current = self.node(index).right;
+        }
+        let len = order.len();
+        let mut old = core::mem::replace(&mut self.nodes, Vec::with_capacity(len));
+        let mut values = order.into_iter().map(|index| old[index].take().unwrap().value);
+        self.free = Vec::new();
+        self.root = self.build_sorted(len, &mut values);
+    }
+
+    // Build a balanced subtree from the next `len` sorted values, pushing its nodes in sorted order
+    fn build_sorted<I: Iterator<Item = T>>(&mut self, len: usize, values: &mut I) -> Option<usize> {
+        if len == 0 {
+            return None;
+        }
+        let left = self.build_sorted(len / 2, values);
+        self.nodes.push(Some(ArenaNode {
+            value: values.next().unwrap(),
+            left,
+            right: None,
+        }));
+        let index = self.nodes.len() - 1;
+        let right = self.build_sorted(len - len / 2 - 1, values);
+        self.node_mut(index).right = right;
+        Some(index)
+    }
+
+    /// Number of slots in the arena, live or free
+    pub fn slots(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Remove every value, keeping the arena's allocation for reuse
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = None;
+    }
+}
+
+impl<T: Ord> Default for ArenaBST<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for ArenaBST<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = ArenaBST::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+// Duplicate values are skipped, just as `insert` would reject them
+impl<T: Ord> Extend<T> for ArenaBST<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            let _ = self.insert(value);
+        }
+    }
+}
+
+/// Lazy in-order iterator over an `ArenaBST`, yielding references to the values in sorted order
+pub struct ArenaIter<'a, T: Ord> {
+    arena: &'a ArenaBST<T>,
+    stack: Vec<usize>,
+}
+
+impl<'a, T: Ord> ArenaIter<'a, T> {
+    // Push a slot and its chain of left descendants, so the smallest pending value is on top
+    fn push_left_spine(&mut self, mut index: Option<usize>) {
+        while let Some(current) = index {
+            self.stack.push(current);
+            index = self.arena.node(current).left;
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for ArenaIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.stack.pop()?;
+        let node = self.arena.node(index);
+        self.push_left_spine(node.right);
+        Some(&node.value)
+    }
+}
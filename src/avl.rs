@@ -0,0 +1,253 @@
+use crate::{BSTError, InOrderIter, IntoIter, Natural, Node, OrderedSet};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+// AVL-specific node operations. They recurse, which is safe here since rebalancing keeps the
+// height logarithmic.
+impl<T: Ord> Node<T> {
+    // Insert a value below the given link, rebalancing every node on the way back up
+    fn avl_insert(link: &mut Option<Box<Node<T>>>, value: T) -> Result<(), BSTError<T>> {
+        let Some(node) = link.as_mut() else {
+            *link = Some(Box::new(Node::new(value)));
+            return Ok(());
+        };
+        match value.cmp(&node.value) {
+            Ordering::Less => Node::avl_insert(&mut node.left, value)?,
+            Ordering::Greater => Node::avl_insert(&mut node.right, value)?,
+            Ordering::Equal => return Err(BSTError::DuplicateValue(value)),
+        }
+        Node::rebalance(link);
+        Ok(())
+    }
+
+    // Remove a value below the given link, rebalancing every node on the way back up
+    fn avl_remove(link: &mut Option<Box<Node<T>>>, value: &T) -> Option<T> {
+        let node = link.as_mut()?;
+        let removed = match value.cmp(&node.value) {
+            Ordering::Less => Node::avl_remove(&mut node.left, value)?,
+            Ordering::Greater => Node::avl_remove(&mut node.right, value)?,
+            Ordering::Equal => {
+                let mut node = link.take().unwrap();
+                *link = match (node.left.take(), node.right.take()) {
+                    (None, right) => right,
+                    (left, None) => left,
+                    (left, Some(right)) => {
+                        // Node has two children, lift the in-order successor (minimum in the right subtree) into its place
+                        let mut right = Some(right);
+                        let mut successor = Node::avl_take_min(&mut right);
+                        successor.left = left;
+                        successor.right = right;
+                        Some(successor)
+                    }
+                };
+                node.value
+            }
+        };
+        Node::rebalance(link);
+        Some(removed)
+    }
+
+    // Detach the node with the smallest value below a non-empty link, rebalancing the nodes above it
+    fn avl_take_min(link: &mut Option<Box<Node<T>>>) -> Box<Node<T>> {
+        let node = link.as_mut().unwrap();
+        if node.left.is_none() {
+            let mut min = link.take().unwrap();
+            *link = min.right.take();
+            return min;
+        }
+        let min = Node::avl_take_min(&mut node.left);
+        Node::rebalance(link);
+        min
+    }
+}
+
+/// A binary search tree that rebalances itself with AVL rotations on every insert and delete,
+/// so its height stays below 1.44 log2(n + 2) whatever the insertion order
+#[derive(Debug, Clone)]
+pub struct AvlTree<T: Ord> {
+    root: Option<Box<Node<T>>>,
+}
+
+// Implement methods for the AVL tree
+impl<T: Ord> AvlTree<T> {
+    /// Create a new empty AVL tree
+    pub fn new() -> Self {
+        AvlTree { root: None }
+    }
+
+    /// Insert a value, rebalancing as needed, in O(log n)
+    pub fn insert(&mut self, value: T) -> Result<(), BSTError<T>> {
+        Node::avl_insert(&mut self.root, value)
+    }
+
+    /// Search for a value in the tree
+    pub fn search(&self, value: T) -> bool {
+        self.contains(&value)
+    }
+
+    /// Check whether a value is in the tree without taking ownership of the query
+    pub fn contains(&self, value: &T) -> bool {
+        Node::find(self.root.as_deref(), value, &Natural).is_some()
+    }
+
+    /// Get a reference to the stored value equal to `value`
+    pub fn get(&self, value: &T) -> Option<&T> {
+        Node::find(self.root.as_deref(), value, &Natural).map(|node| &node.value)
+    }
+
+    /// Delete a value, rebalancing as needed, in O(log n). The value is handed back in
+    /// `ValueNotFound` if it isn't there.
+    pub fn delete(&mut self, value: T) -> Result<(), BSTError<T>> {
+        match self.remove(&value) {
+            Some(_) => Ok(()),
+            None => Err(BSTError::ValueNotFound(value)),
+        }
+    }
+
+    /// Remove the value equal to `value` and return the stored value, or `None` if there is none
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        Node::avl_remove(&mut self.root, value)
+    }
+
+    /// Find the minimum value in the tree
+    pub fn find_min(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| root.find_min())
+    }
+
+    /// Find the maximum value in the tree
+    pub fn find_max(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| root.find_max())
+    }
+
+    /// Iterate over the values in sorted order
+    pub fn iter(&self) -> InOrderIter<'_, T> {
+        InOrderIter::new(self.root.as_deref())
+    }
+
+    /// In-order traversal
+    pub fn in_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.in_order_traversal(&mut result);
+        }
+        result
+    }
+
+    /// Pre-order traversal
+    pub fn pre_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.pre_order_traversal(&mut result);
+        }
+        result
+    }
+
+    /// Post-order traversal
+    pub fn post_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.post_order_traversal(&mut result);
+        }
+        result
+    }
+
+    /// Number of values in the tree, in O(1)
+    pub fn len(&self) -> usize {
+        Node::size_of(&self.root)
+    }
+
+    /// Check if the tree holds no values, in O(1)
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Height of the tree in nodes (0 when empty), in O(1)
+    pub fn height(&self) -> usize {
+        Node::height_of(&self.root)
+    }
+
+    /// Check if the tree is height-balanced, which the rotations should always guarantee
+    pub fn is_balanced(&self) -> bool {
+        self.root.as_ref().is_none_or(|root| root.is_balanced())
+    }
+
+    /// Recompute the size and height of every node and compare them with the cached values.
+    /// Returns the values of the nodes whose caches are stale, in post-order, or `Ok(())` if all agree.
+    pub fn verify_caches(&self) -> Result<(), Vec<T>>
+    where
+        T: Clone,
+    {
+        let mut mismatched = Vec::new();
+        Node::verify_caches(&self.root, &mut mismatched);
+        if mismatched.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatched)
+        }
+    }
+}
+
+impl<T: Ord> OrderedSet<T> for AvlTree<T> {
+    fn first(&self) -> Option<&T> {
+        self.find_min()
+    }
+
+    fn last(&self) -> Option<&T> {
+        self.find_max()
+    }
+
+    fn nth(&self, n: usize) -> Option<&T> {
+        Node::select(self.root.as_deref(), n)
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        self.contains(value)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: Ord> Default for AvlTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for AvlTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = AvlTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+// Duplicate values are skipped, just as `insert` would reject them
+impl<T: Ord> Extend<T> for AvlTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            let _ = self.insert(value);
+        }
+    }
+}
+
+impl<T: Ord> IntoIterator for AvlTree<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    // Consume the tree, moving the values out in sorted order
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.root)
+    }
+}
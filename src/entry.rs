@@ -0,0 +1,94 @@
+use crate::{BinarySearchTree, Natural, Node};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// A view into a single value of a `BinarySearchTree`, which is either stored or missing,
+/// returned by `BinarySearchTree::entry`
+pub enum Entry<'a, T, C = Natural> {
+    /// There is no equal value in the tree yet
+    Vacant(VacantEntry<'a, T, C>),
+    /// An equal value is already stored in the tree
+    Occupied(OccupiedEntry<'a, T, C>),
+}
+
+/// An entry for a value that is already stored in the tree
+pub struct OccupiedEntry<'a, T, C = Natural> {
+    pub(crate) tree: &'a mut BinarySearchTree<T, C>,
+    // Directions from the root down to the stored node
+    pub(crate) path: Vec<Ordering>,
+}
+
+/// An entry for a value that isn't in the tree yet, holding the value until it's inserted
+pub struct VacantEntry<'a, T, C = Natural> {
+    pub(crate) tree: &'a mut BinarySearchTree<T, C>,
+    // Directions from the root down to the empty link the value belongs in
+    pub(crate) path: Vec<Ordering>,
+    pub(crate) value: T,
+}
+
+impl<'a, T, C> Entry<'a, T, C> {
+    /// Insert the value if the entry is vacant, and return the stored value either way
+    pub fn or_insert(self) -> &'a T {
+        match self {
+            Entry::Vacant(entry) => entry.insert(),
+            Entry::Occupied(entry) => entry.into_ref(),
+        }
+    }
+
+    /// Modify the stored value in place if the entry is occupied. The change mustn't alter how
+    /// the value compares, or the tree's ordering breaks.
+    pub fn and_modify<F: FnOnce(&mut T)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(ref mut entry) = self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    /// The stored value if the entry is occupied, or the value waiting to be inserted otherwise
+    pub fn get(&self) -> &T {
+        match self {
+            Entry::Vacant(entry) => entry.get(),
+            Entry::Occupied(entry) => entry.get(),
+        }
+    }
+}
+
+impl<'a, T, C> OccupiedEntry<'a, T, C> {
+    /// The stored value
+    pub fn get(&self) -> &T {
+        &Node::node_at(&self.tree.root, &self.path).value
+    }
+
+    /// Mutable access to the stored value. The change mustn't alter how the value compares, or
+    /// the tree's ordering breaks.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut Node::link_at(&mut self.tree.root, &self.path).as_mut().unwrap().value
+    }
+
+    /// Turn the entry into a reference to the stored value that lives as long as the tree borrow
+    pub fn into_ref(self) -> &'a T {
+        &Node::node_at(&self.tree.root, &self.path).value
+    }
+
+    /// Remove the stored value from the tree and return it
+    pub fn remove(self) -> T {
+        Node::remove_at(&mut self.tree.root, &self.path)
+    }
+}
+
+impl<'a, T, C> VacantEntry<'a, T, C> {
+    /// The value waiting to be inserted
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Take the value back without inserting it
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Insert the value where the lookup ended, and return a reference to it
+    pub fn insert(self) -> &'a T {
+        Node::attach_at(&mut self.tree.root, &self.path, self.value)
+    }
+}
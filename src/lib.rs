@@ -0,0 +1,3980 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::hash::{Hash, Hasher};
+use core::ops::Bound;
+#[cfg(feature = "std")]
+use std::collections::hash_map::DefaultHasher;
+
+mod arena;
+mod avl;
+mod entry;
+mod map;
+mod multiset;
+mod persistent;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod shared;
+
+pub use arena::{ArenaBST, ArenaIter};
+pub use avl::AvlTree;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use map::{BSTMap, MapIter};
+pub use multiset::BSTMultiset;
+pub use persistent::{PersistentBST, PersistentIter};
+pub use shared::SharedBST;
+
+/// Errors returned by fallible BST operations, carrying the value that caused them where there is one
+#[derive(Debug)]
+pub enum BSTError<T> {
+    DuplicateValue(T),
+    ValueNotFound(T),
+    OverlappingRanges,
+    OutOfRange,
+}
+
+impl<T: fmt::Debug> fmt::Display for BSTError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BSTError::DuplicateValue(value) => write!(f, "Duplicate value: cannot insert {:?} twice", value),
+            BSTError::ValueNotFound(value) => write!(f, "Value not found: {:?} isn't in the tree", value),
+            BSTError::OverlappingRanges => write!(f, "Overlapping ranges: trees must not interleave"),
+            BSTError::OutOfRange => write!(f, "Out of range: values don't fit their position in the tree"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> core::error::Error for BSTError<T> {}
+
+/// Counts from a bulk `insert_all` or `append`: how many values went in and how many were skipped as duplicates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InsertReport {
+    pub inserted: usize,
+    pub duplicates: usize,
+}
+
+/// Shape statistics gathered by `stats` in a single traversal. Depths count edges from the root
+/// and are 0 for an empty BST, whose `height` is 0 as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    pub node_count: usize,
+    pub height: usize,
+    pub leaf_count: usize,
+    pub min_depth: usize,
+    pub max_depth: usize,
+    pub is_balanced: bool,
+}
+
+/// The four set operations on two BSTs, computed together by `set_ops` in one merge walk
+#[derive(Debug, Clone)]
+pub struct SetOps<T, C = Natural> {
+    pub union: BinarySearchTree<T, C>,
+    pub intersection: BinarySearchTree<T, C>,
+    pub difference: BinarySearchTree<T, C>,
+    pub symmetric_difference: BinarySearchTree<T, C>,
+}
+
+/// Decides the order of the values in a BST
+pub trait Comparator<T> {
+    /// Compare two values, as `Ord::cmp` would
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+/// Read access shared by the ordered set types, for code that works with any of them
+pub trait OrderedSet<T> {
+    /// The smallest value
+    fn first(&self) -> Option<&T>;
+    /// The largest value
+    fn last(&self) -> Option<&T>;
+    /// The `n`-th smallest value (0-indexed)
+    fn nth(&self, n: usize) -> Option<&T>;
+    /// Check whether a value is in the set
+    fn contains(&self, value: &T) -> bool;
+    /// Number of values in the set
+    fn len(&self) -> usize;
+    /// Check if the set holds no values
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The natural order of a `T: Ord`, used unless the BST is built with `with_comparator`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Natural;
+
+impl<T: Ord> Comparator<T> for Natural {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+// Any comparison closure, e.g. `|a: &&str, b: &&str| a.len().cmp(&b.len())`
+impl<T, F: Fn(&T, &T) -> Ordering> Comparator<T> for F {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
+
+// Define the structure of a node in the BST
+#[derive(Debug)]
+struct Node<T> {
+    value: T,
+    left: Option<Box<Node<T>>>,
+    right: Option<Box<Node<T>>>,
+    // Number of nodes in the subtree rooted here, this one included
+    size: usize,
+    // Number of nodes on the longest path from here down to a leaf, this one included
+    height: usize,
+}
+
+// Implement methods for the Node
+impl<T> Node<T> {
+    // Create a new node
+    fn new(value: T) -> Self {
+        Node {
+            value,
+            left: None,
+            right: None,
+            size: 1,
+            height: 1,
+        }
+    }
+
+    // Number of nodes below a link, read from the cached size
+    fn size_of(link: &Option<Box<Node<T>>>) -> usize {
+        link.as_ref().map_or(0, |node| node.size)
+    }
+
+    // Height of the subtree below a link, read from the cached height
+    fn height_of(link: &Option<Box<Node<T>>>) -> usize {
+        link.as_ref().map_or(0, |node| node.height)
+    }
+
+    // Copy a subtree with an explicit work stack, children before their parent, so a deep, degenerate
+    // one can't overflow the call stack. The cached sizes and heights are copied along with the values.
+    fn clone_subtree(root: &Node<T>) -> Box<Node<T>>
+    where
+        T: Clone,
+    {
+        let mut pending = vec![(root, false)];
+        let mut built: Vec<Box<Node<T>>> = Vec::new();
+        while let Some((node, children_built)) = pending.pop() {
+            if !children_built {
+                pending.push((node, true));
+                pending.extend(node.right.as_deref().map(|right| (right, false)));
+                pending.extend(node.left.as_deref().map(|left| (left, false)));
+                continue;
+            }
+            // The right subtree was finished last, so its copy is on top
+            let right = node.right.as_ref().map(|_| built.pop().unwrap());
+            let left = node.left.as_ref().map(|_| built.pop().unwrap());
+            built.push(Box::new(Node {
+                value: node.value.clone(),
+                left,
+                right,
+                size: node.size,
+                height: node.height,
+            }));
+        }
+        built.pop().unwrap()
+    }
+
+    // Free a detached subtree with an explicit work stack, so a deep, degenerate one can't overflow the call stack
+    fn dismantle(link: Option<Box<Node<T>>>) {
+        let mut stack: Vec<Box<Node<T>>> = link.into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+
+    // Recompute the cached size and height from the children's
+    fn update(&mut self) {
+        self.size = 1 + Node::size_of(&self.left) + Node::size_of(&self.right);
+        self.height = 1 + Node::height_of(&self.left).max(Node::height_of(&self.right));
+    }
+
+    // Record the directions taken from `link` down to the node holding `value`, or to the empty
+    // link where it would be inserted, and whether such a node was found
+    fn path_to<C: Comparator<T>>(mut current: Option<&Node<T>>, value: &T, cmp: &C) -> (Vec<Ordering>, bool) {
+        let mut path = Vec::new();
+        while let Some(node) = current {
+            let ordering = cmp.compare(value, &node.value);
+            current = match ordering {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return (path, true),
+            };
+            path.push(ordering);
+        }
+        (path, false)
+    }
+
+    // Recompute the caches of every node reached by following `path` from `link`, deepest first,
+    // after a change somewhere along it. Nodes off the path must already be up to date.
+    // A read-only pass works out the new values bottom-up, then a second pass stores them top-down.
+    fn fix_path(link: &mut Option<Box<Node<T>>>, path: &[Ordering]) {
+        let mut nodes = Vec::new();
+        let mut current = link.as_deref();
+        while let Some(node) = current {
+            nodes.push(node);
+            current = match path.get(nodes.len() - 1) {
+                Some(Ordering::Less) => node.left.as_deref(),
+                Some(Ordering::Greater) => node.right.as_deref(),
+                _ => None,
+            };
+        }
+        let mut caches = vec![(0, 0); nodes.len()];
+        for (depth, node) in nodes.iter().enumerate().rev() {
+            let child = |link: &Option<Box<Node<T>>>| (Node::size_of(link), Node::height_of(link));
+            let (mut left, mut right) = (child(&node.left), child(&node.right));
+            if depth + 1 < nodes.len() {
+                match path[depth] {
+                    Ordering::Less => left = caches[depth + 1],
+                    _ => right = caches[depth + 1],
+                }
+            }
+            caches[depth] = (1 + left.0 + right.0, 1 + left.1.max(right.1));
+        }
+        let mut link = link;
+        for (depth, (size, height)) in caches.into_iter().enumerate() {
+            let node = link.as_mut().unwrap();
+            node.size = size;
+            node.height = height;
+            link = match path.get(depth) {
+                Some(Ordering::Less) => &mut node.left,
+                _ => &mut node.right,
+            };
+        }
+    }
+
+    // Build a balanced subtree from a sorted slice, rooting it at the middle element
+    fn from_sorted_slice(items: &[T]) -> Option<Box<Node<T>>>
+    where
+        T: Clone,
+    {
+        if items.is_empty() {
+            return None;
+        }
+        let mid = items.len() / 2;
+        let mut node = Box::new(Node::new(items[mid].clone()));
+        node.left = Node::from_sorted_slice(&items[..mid]);
+        node.right = Node::from_sorted_slice(&items[mid + 1..]);
+        node.update();
+        Some(node)
+    }
+
+    // Build a balanced subtree from the next `len` values of an iterator in sorted order, moving
+    // each value in: the left half is built first, then the middle value, then the right half
+    fn from_sorted_iter<I: Iterator<Item = T>>(len: usize, values: &mut I) -> Option<Box<Node<T>>> {
+        if len == 0 {
+            return None;
+        }
+        let left = Node::from_sorted_iter(len / 2, values);
+        let mut node = Box::new(Node::new(values.next()?));
+        node.left = left;
+        node.right = Node::from_sorted_iter(len - len / 2 - 1, values);
+        node.update();
+        Some(node)
+    }
+
+    // Insert a value below the given link, descending iteratively
+    fn insert<C: Comparator<T>>(link: &mut Option<Box<Node<T>>>, value: T, cmp: &C) -> Result<(), BSTError<T>> {
+        let (path, found) = Node::path_to(link.as_deref(), &value, cmp);
+        if found {
+            return Err(BSTError::DuplicateValue(value));
+        }
+        Node::attach_at(link, &path, value);
+        Ok(())
+    }
+
+    // Insert a value below the given link, or swap it in for an equal stored value and hand that back
+    fn insert_or_replace<C: Comparator<T>>(link: &mut Option<Box<Node<T>>>, value: T, cmp: &C) -> Option<T> {
+        let (path, found) = Node::path_to(link.as_deref(), &value, cmp);
+        if found {
+            let node = Node::link_at(link, &path).as_mut().unwrap();
+            return Some(core::mem::replace(&mut node.value, value));
+        }
+        Node::attach_at(link, &path, value);
+        None
+    }
+
+    // Follow a path from `path_to` down to the empty link it ends at and put a new leaf holding
+    // `value` there, growing the size and height of every node passed on the way, so the caches
+    // are fixed in the same descent
+    fn attach_at<'a>(mut link: &'a mut Option<Box<Node<T>>>, path: &[Ordering], value: T) -> &'a T {
+        for (depth, ordering) in path.iter().enumerate() {
+            let node = link.as_mut().unwrap();
+            node.size += 1;
+            node.height = node.height.max(path.len() - depth + 1);
+            link = match ordering {
+                Ordering::Less => &mut node.left,
+                _ => &mut node.right,
+            };
+        }
+        &link.insert(Box::new(Node::new(value))).value
+    }
+
+    // Follow a path from `path_to` down to the link it leads to
+    fn link_at<'a>(mut link: &'a mut Option<Box<Node<T>>>, path: &[Ordering]) -> &'a mut Option<Box<Node<T>>> {
+        for ordering in path {
+            let node = link.as_mut().unwrap();
+            link = match ordering {
+                Ordering::Less => &mut node.left,
+                _ => &mut node.right,
+            };
+        }
+        link
+    }
+
+    // Follow a path from `path_to` down to the node it leads to, which must exist
+    fn node_at<'a>(link: &'a Option<Box<Node<T>>>, path: &[Ordering]) -> &'a Node<T> {
+        let mut node = link.as_deref().unwrap();
+        for ordering in path {
+            node = match ordering {
+                Ordering::Less => node.left.as_deref().unwrap(),
+                _ => node.right.as_deref().unwrap(),
+            };
+        }
+        node
+    }
+
+    // Return the value a path from `path_to` leads to, first filling the empty link it ends at
+    // with `make()` if nothing was found
+    fn get_or_fill<'a, F: FnOnce() -> T>(link: &'a mut Option<Box<Node<T>>>, path: &[Ordering], found: bool, make: F) -> &'a T {
+        if found {
+            &Node::node_at(link, path).value
+        } else {
+            Node::attach_at(link, path, make())
+        }
+    }
+
+    // Find the node holding a value, descending iteratively
+    fn find<'a, C: Comparator<T>>(mut current: Option<&'a Node<T>>, value: &T, cmp: &C) -> Option<&'a Node<T>> {
+        while let Some(node) = current {
+            current = match cmp.compare(value, &node.value) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    // Find the minimum value in the BST
+    fn find_min(&self) -> &T {
+        let mut node = self;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        &node.value
+    }
+
+    // Find the maximum value in the BST
+    fn find_max(&self) -> &T {
+        let mut node = self;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        &node.value
+    }
+
+    // Remove a value below the given link, descending iteratively, and hand back the stored value
+    fn remove<C: Comparator<T>>(link: &mut Option<Box<Node<T>>>, value: &T, cmp: &C) -> Option<T> {
+        let (path, found) = Node::path_to(link.as_deref(), value, cmp);
+        found.then(|| Node::remove_at(link, &path))
+    }
+
+    // Remove the node a path leads to, which must exist, and hand back its value
+    fn remove_at(link: &mut Option<Box<Node<T>>>, path: &[Ordering]) -> T {
+        let slot = Node::link_at(link, path);
+        let mut node = slot.take().unwrap();
+        *slot = match (node.left.take(), node.right.take()) {
+            (None, right) => right,
+            (left, None) => left,
+            (left, Some(right)) => {
+                // Node has two children, lift the in-order successor (minimum in the right subtree) into its place
+                let mut right = Some(right);
+                let mut successor = Node::take_min(&mut right);
+                successor.left = left;
+                successor.right = right;
+                successor.update();
+                Some(successor)
+            }
+        };
+        Node::fix_path(link, path);
+        node.value
+    }
+
+    // Detach the node with the smallest value below a non-empty link, splicing its right child into its place.
+    // The detached node keeps stale caches, which the caller resets if it relinks the node.
+    fn take_min(link: &mut Option<Box<Node<T>>>) -> Box<Node<T>> {
+        let mut path = Vec::new();
+        let mut slot = &mut *link;
+        while slot.as_ref().unwrap().left.is_some() {
+            slot = &mut slot.as_mut().unwrap().left;
+            path.push(Ordering::Less);
+        }
+        let mut min = slot.take().unwrap();
+        *slot = min.right.take();
+        Node::fix_path(link, &path);
+        min
+    }
+
+    // Detach the node with the largest value below a non-empty link, splicing its left child into its place.
+    // The detached node keeps stale caches, which the caller resets if it relinks the node.
+    fn take_max(link: &mut Option<Box<Node<T>>>) -> Box<Node<T>> {
+        let mut path = Vec::new();
+        let mut slot = &mut *link;
+        while slot.as_ref().unwrap().right.is_some() {
+            slot = &mut slot.as_mut().unwrap().right;
+            path.push(Ordering::Greater);
+        }
+        let mut max = slot.take().unwrap();
+        *slot = max.left.take();
+        Node::fix_path(link, &path);
+        max
+    }
+
+    // Join two subtrees around a pivot that lies between every value of `low` and every value of
+    // `high`, in O(height). The pivot takes the shorter subtree and the part of the taller one's
+    // inner spine that is as tall, then the spine nodes above it are relinked bottom-up and
+    // rebalanced, so joining two height-balanced subtrees gives a height-balanced one.
+    fn join(low: Option<Box<Node<T>>>, mut pivot: Box<Node<T>>, high: Option<Box<Node<T>>>) -> Option<Box<Node<T>>> {
+        let down_right = Node::height_of(&low) >= Node::height_of(&high);
+        let (mut taller, shorter) = if down_right { (low, high) } else { (high, low) };
+        let limit = Node::height_of(&shorter) + 1;
+        let mut spine = Vec::new();
+        while Node::height_of(&taller) > limit {
+            let mut node = taller.unwrap();
+            taller = if down_right { node.right.take() } else { node.left.take() };
+            spine.push(node);
+        }
+        (pivot.left, pivot.right) = if down_right { (taller, shorter) } else { (shorter, taller) };
+        let mut joined = Some(pivot);
+        Node::rebalance(&mut joined);
+        while let Some(mut parent) = spine.pop() {
+            if down_right {
+                parent.right = joined;
+            } else {
+                parent.left = joined;
+            }
+            joined = Some(parent);
+            Node::rebalance(&mut joined);
+        }
+        joined
+    }
+
+    // Refresh the caches at a link and, if its subtrees differ in height by two, restore the AVL
+    // invariant with a single (LL/RR) or double (LR/RL) rotation
+    fn rebalance(link: &mut Option<Box<Node<T>>>) {
+        let Some(node) = link.as_mut() else {
+            return;
+        };
+        node.update();
+        match node.balance_factor() {
+            2.. => {
+                if node.left.as_ref().unwrap().balance_factor() < 0 {
+                    Node::rotate_left(&mut node.left);
+                }
+                Node::rotate_right(link);
+            }
+            ..=-2 => {
+                if node.right.as_ref().unwrap().balance_factor() > 0 {
+                    Node::rotate_right(&mut node.right);
+                }
+                Node::rotate_left(link);
+            }
+            _ => {}
+        }
+    }
+
+    // Detach every value on the `side` of `threshold` (`Less` for the values below it, `Greater` for
+    // those above) in one descent: a node on that side goes along with its whole subtree on that
+    // side, and its other child takes its place. Only the caches of the kept nodes walked past change.
+    fn prune<C: Comparator<T>>(link: &mut Option<Box<Node<T>>>, threshold: &T, side: Ordering, cmp: &C) {
+        let mut path = Vec::new();
+        let mut slot = &mut *link;
+        while let Some(node) = slot.as_ref() {
+            if cmp.compare(&node.value, threshold) == side {
+                let mut node = slot.take().unwrap();
+                let (kept, dropped) = match side {
+                    Ordering::Less => (node.right.take(), node.left.take()),
+                    _ => (node.left.take(), node.right.take()),
+                };
+                Node::dismantle(dropped);
+                *slot = kept;
+            } else {
+                let node = slot.as_mut().unwrap();
+                slot = match side {
+                    Ordering::Less => &mut node.left,
+                    _ => &mut node.right,
+                };
+                path.push(side);
+            }
+        }
+        Node::fix_path(link, &path);
+    }
+
+    // Visit the values in order with O(1) extra space by reversing links on the way down (Schorr-Waite).
+    // Each ancestor on the way down stores the chain above it in the child field the walk took, and is
+    // tagged with a size of 0 when that was its right field; the tag lets the walk climb back and restore
+    // that field, after which update() recomputes the size. Threading predecessors back to their successors
+    // as in a Morris traversal would need two boxes owning one node, so ownership is moved instead.
+    // The walk state lives in a guard that relinks the tree when dropped, so a panic in `f` loses nothing.
+    fn for_each_reversing_links<F: FnMut(&T)>(link: &mut Option<Box<Node<T>>>, f: &mut F) {
+        let Some(root) = link.take() else {
+            return;
+        };
+        let mut walk = LinkReversal {
+            link,
+            node: Some(root),
+            up: None,
+        };
+        'down: loop {
+            let mut node = walk.node.take().unwrap();
+            while let Some(left) = node.left.take() {
+                node.left = walk.up.take();
+                walk.up = Some(node);
+                node = left;
+            }
+            walk.node = Some(node);
+            f(&walk.node.as_ref().unwrap().value);
+            // The node just visited goes on into its right subtree if it has one; otherwise its subtree
+            // is done, so climb past the ancestors it was reached through from the right
+            loop {
+                let mut node = walk.node.take().unwrap();
+                if let Some(right) = node.right.take() {
+                    node.right = walk.up.take();
+                    node.size = 0;
+                    walk.up = Some(node);
+                    walk.node = Some(right);
+                    continue 'down;
+                }
+                let mut parent = loop {
+                    let Some(mut parent) = walk.up.take() else {
+                        walk.node = Some(node);
+                        return;
+                    };
+                    if parent.size != 0 {
+                        break parent;
+                    }
+                    walk.up = parent.right.replace(node);
+                    parent.update();
+                    node = parent;
+                };
+                walk.up = parent.left.replace(node);
+                walk.node = Some(parent);
+                f(&walk.node.as_ref().unwrap().value);
+            }
+        }
+    }
+
+    // Thread an accumulator through the values in order, without allocating
+    fn fold_in_order<B, F: FnMut(B, &T) -> B>(&self, mut acc: B, f: &mut F) -> B {
+        if let Some(ref left) = self.left {
+            acc = left.fold_in_order(acc, f);
+        }
+        acc = f(acc, &self.value);
+        if let Some(ref right) = self.right {
+            acc = right.fold_in_order(acc, f);
+        }
+        acc
+    }
+
+    // In-order traversal (left, root, right)
+    fn in_order_traversal(&self, result: &mut Vec<T>)
+    where
+        T: Clone,
+    {
+        if let Some(ref left) = self.left {
+            left.in_order_traversal(result);
+        }
+        result.push(self.value.clone());
+        if let Some(ref right) = self.right {
+            right.in_order_traversal(result);
+        }
+    }
+
+    // In-order traversal restricted to the values within the bounds, skipping subtrees that can't hold any
+    fn range_traversal<C: Comparator<T>>(&self, low: Bound<&T>, high: Bound<&T>, result: &mut Vec<T>, cmp: &C)
+    where
+        T: Clone,
+    {
+        let (go_left, above_low) = match low.map(|low| cmp.compare(&self.value, low)) {
+            Bound::Included(ordering) => (ordering.is_gt(), ordering.is_ge()),
+            Bound::Excluded(ordering) => (ordering.is_gt(), ordering.is_gt()),
+            Bound::Unbounded => (true, true),
+        };
+        let (go_right, below_high) = match high.map(|high| cmp.compare(&self.value, high)) {
+            Bound::Included(ordering) => (ordering.is_lt(), ordering.is_le()),
+            Bound::Excluded(ordering) => (ordering.is_lt(), ordering.is_lt()),
+            Bound::Unbounded => (true, true),
+        };
+        if go_left {
+            if let Some(ref left) = self.left {
+                left.range_traversal(low, high, result, cmp);
+            }
+        }
+        if above_low && below_high {
+            result.push(self.value.clone());
+        }
+        if go_right {
+            if let Some(ref right) = self.right {
+                right.range_traversal(low, high, result, cmp);
+            }
+        }
+    }
+
+    // Count the values within `[low, high]`, skipping subtrees that can't hold any
+    fn count_in_range<C: Comparator<T>>(&self, low: &T, high: &T, cmp: &C) -> usize {
+        let above_low = cmp.compare(&self.value, low);
+        let below_high = cmp.compare(&self.value, high);
+        let mut count = 0;
+        if above_low.is_gt() {
+            count += self.left.as_ref().map_or(0, |left| left.count_in_range(low, high, cmp));
+        }
+        if above_low.is_ge() && below_high.is_le() {
+            count += 1;
+        }
+        if below_high.is_lt() {
+            count += self.right.as_ref().map_or(0, |right| right.count_in_range(low, high, cmp));
+        }
+        count
+    }
+
+    // In-order traversal pairing each value with the values of its direct children
+    fn adjacency_traversal(&self, result: &mut Vec<(T, Vec<T>)>)
+    where
+        T: Clone,
+    {
+        if let Some(ref left) = self.left {
+            left.adjacency_traversal(result);
+        }
+        let children = [&self.left, &self.right]
+            .into_iter()
+            .flatten()
+            .map(|child| child.value.clone())
+            .collect();
+        result.push((self.value.clone(), children));
+        if let Some(ref right) = self.right {
+            right.adjacency_traversal(result);
+        }
+    }
+
+    // In-order traversal pairing each value with its depth (this node = 0), with an explicit stack
+    // so degenerate trees don't recurse once per level
+    fn in_order_with_depth(&self, result: &mut Vec<(T, usize)>)
+    where
+        T: Clone,
+    {
+        let mut stack = Vec::new();
+        let mut current = Some((self, 0));
+        loop {
+            while let Some((node, depth)) = current {
+                stack.push((node, depth));
+                current = node.left.as_deref().map(|left| (left, depth + 1));
+            }
+            let Some((node, depth)) = stack.pop() else {
+                return;
+            };
+            result.push((node.value.clone(), depth));
+            current = node.right.as_deref().map(|right| (right, depth + 1));
+        }
+    }
+
+    // Pre-order traversal (root, left, right) with an explicit stack, pushing the right child
+    // first so the left one is popped first
+    fn pre_order_traversal(&self, result: &mut Vec<T>)
+    where
+        T: Clone,
+    {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            result.push(node.value.clone());
+            stack.extend(node.right.as_deref());
+            stack.extend(node.left.as_deref());
+        }
+    }
+
+    // Post-order traversal (left, right, root) with two stacks: visiting root, right, left with an
+    // explicit stack fills the output in exactly the reverse of post-order, so it's reversed at the end
+    fn post_order_traversal(&self, result: &mut Vec<T>)
+    where
+        T: Clone,
+    {
+        let start = result.len();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            result.push(node.value.clone());
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+        result[start..].reverse();
+    }
+
+    // Find the value at in-order position `k`, steering by the left subtree sizes
+    fn select(mut current: Option<&Node<T>>, mut k: usize) -> Option<&T> {
+        while let Some(node) = current {
+            let left_size = Node::size_of(&node.left);
+            current = match k.cmp(&left_size) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => {
+                    k -= left_size + 1;
+                    node.right.as_deref()
+                }
+            };
+        }
+        None
+    }
+
+    // Count the values strictly less than `value`, adding up the left subtrees passed on the way down
+    fn rank<C: Comparator<T>>(mut current: Option<&Node<T>>, value: &T, cmp: &C) -> usize {
+        let mut rank = 0;
+        while let Some(node) = current {
+            current = match cmp.compare(value, &node.value) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Equal => return rank + Node::size_of(&node.left),
+                Ordering::Greater => {
+                    rank += Node::size_of(&node.left) + 1;
+                    node.right.as_deref()
+                }
+            };
+        }
+        rank
+    }
+
+    // Count the number of nodes in the BST
+    fn count_nodes(&self) -> usize {
+        let mut count = 1;
+        if let Some(ref left) = self.left {
+            count += left.count_nodes();
+        }
+        if let Some(ref right) = self.right {
+            count += right.count_nodes();
+        }
+        count
+    }
+
+    // Longest path, in edges, between two nodes of this subtree: the widest bend through any node,
+    // which joins the cached heights of its two children
+    fn diameter(&self) -> usize {
+        let through_here = Node::height_of(&self.left) + Node::height_of(&self.right);
+        let left = self.left.as_ref().map_or(0, |left| left.diameter());
+        let right = self.right.as_ref().map_or(0, |right| right.diameter());
+        through_here.max(left).max(right)
+    }
+
+    // Count full nodes (two children) and half nodes (one child) in a single traversal
+    fn count_by_children(&self) -> (usize, usize) {
+        let (mut full, mut half) = match (&self.left, &self.right) {
+            (Some(_), Some(_)) => (1, 0),
+            (None, None) => (0, 0),
+            _ => (0, 1),
+        };
+        if let Some(ref left) = self.left {
+            let (left_full, left_half) = left.count_by_children();
+            full += left_full;
+            half += left_half;
+        }
+        if let Some(ref right) = self.right {
+            let (right_full, right_half) = right.count_by_children();
+            full += right_full;
+            half += right_half;
+        }
+        (full, half)
+    }
+
+    // Check that the heights of the two subtrees differ by at most one here and at every node below
+    fn is_balanced(&self) -> bool {
+        Node::height_of(&self.left).abs_diff(Node::height_of(&self.right)) <= 1
+            && self.left.as_ref().is_none_or(|left| left.is_balanced())
+            && self.right.as_ref().is_none_or(|right| right.is_balanced())
+    }
+
+    // Height of the left subtree minus the height of the right one
+    fn balance_factor(&self) -> isize {
+        Node::height_of(&self.left) as isize - Node::height_of(&self.right) as isize
+    }
+
+    // Recompute the size and height of every node in a post-order pass, collecting the values of
+    // nodes whose caches disagree, and return the fresh size and height of this subtree
+    fn verify_caches(link: &Option<Box<Node<T>>>, mismatched: &mut Vec<T>) -> (usize, usize)
+    where
+        T: Clone,
+    {
+        let Some(node) = link else {
+            return (0, 0);
+        };
+        let (left_size, left_height) = Node::verify_caches(&node.left, mismatched);
+        let (right_size, right_height) = Node::verify_caches(&node.right, mismatched);
+        let size = 1 + left_size + right_size;
+        let height = 1 + left_height.max(right_height);
+        if node.size != size || node.height != height {
+            mismatched.push(node.value.clone());
+        }
+        (size, height)
+    }
+
+    // Cut off every node deeper than `max_depth`, collecting the removed values in order
+    fn truncate(&mut self, depth: usize, max_depth: usize, removed: &mut Vec<T>) {
+        if depth == max_depth {
+            removed.extend(IntoIter::new(self.left.take()));
+            removed.extend(IntoIter::new(self.right.take()));
+        } else {
+            if let Some(ref mut left) = self.left {
+                left.truncate(depth + 1, max_depth, removed);
+            }
+            if let Some(ref mut right) = self.right {
+                right.truncate(depth + 1, max_depth, removed);
+            }
+        }
+        self.update();
+    }
+
+    // Track the shallowest leaf and the deepest node in a single pre-order walk
+    fn depth_extremes<'a>(
+        &'a self,
+        depth: usize,
+        shallowest_leaf: &mut Option<(&'a T, usize)>,
+        deepest_node: &mut Option<(&'a T, usize)>,
+    ) {
+        let is_leaf = self.left.is_none() && self.right.is_none();
+        if is_leaf && shallowest_leaf.is_none_or(|(_, best)| depth < best) {
+            *shallowest_leaf = Some((&self.value, depth));
+        }
+        if deepest_node.is_none_or(|(_, best)| depth > best) {
+            *deepest_node = Some((&self.value, depth));
+        }
+        if let Some(ref left) = self.left {
+            left.depth_extremes(depth + 1, shallowest_leaf, deepest_node);
+        }
+        if let Some(ref right) = self.right {
+            right.depth_extremes(depth + 1, shallowest_leaf, deepest_node);
+        }
+    }
+
+    // Rotate right at the given link, lifting the left child into the node's place
+    fn rotate_right(link: &mut Option<Box<Node<T>>>) {
+        if let Some(mut node) = link.take() {
+            match node.left.take() {
+                Some(mut left) => {
+                    node.left = left.right.take();
+                    node.update();
+                    left.right = Some(node);
+                    left.update();
+                    *link = Some(left);
+                }
+                None => *link = Some(node),
+            }
+        }
+    }
+
+    // Rotate left at the given link, lifting the right child into the node's place
+    fn rotate_left(link: &mut Option<Box<Node<T>>>) {
+        if let Some(mut node) = link.take() {
+            match node.right.take() {
+                Some(mut right) => {
+                    node.right = right.left.take();
+                    node.update();
+                    right.left = Some(node);
+                    right.update();
+                    *link = Some(right);
+                }
+                None => *link = Some(node),
+            }
+        }
+    }
+
+    // Swap the children of every node below a link, using an explicit work stack. Sizes and heights
+    // are symmetric, so the caches stay valid.
+    fn invert(link: &mut Option<Box<Node<T>>>) {
+        let mut stack: Vec<&mut Node<T>> = link.as_deref_mut().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            core::mem::swap(&mut node.left, &mut node.right);
+            stack.extend(node.left.as_deref_mut());
+            stack.extend(node.right.as_deref_mut());
+        }
+    }
+
+    // Append Graphviz statements for this subtree, adding numbered invisible placeholders
+    // for a missing child so the remaining child is drawn on its correct side
+    fn write_dot(&self, out: &mut String, placeholders: &mut usize)
+    where
+        T: fmt::Display,
+    {
+        let id = dot_id(&self.value);
+        out.push_str(&format!("    {};\n", id));
+        if self.left.is_none() && self.right.is_none() {
+            return;
+        }
+        for child in [&self.left, &self.right] {
+            match child {
+                Some(child) => {
+                    out.push_str(&format!("    {} -> {};\n", id, dot_id(&child.value)));
+                    child.write_dot(out, placeholders);
+                }
+                None => {
+                    out.push_str(&format!("    null{} [shape=point, style=invis];\n", placeholders));
+                    out.push_str(&format!("    {} -> null{} [style=invis];\n", id, placeholders));
+                    *placeholders += 1;
+                }
+            }
+        }
+    }
+
+    // Write the descendants of this node as indented tree lines in pre-order, from an explicit stack
+    // so a deep, degenerate tree can't overflow the call stack. `guides` records, for each level above
+    // the line being written, whether that ancestor still has a sibling below it to draw a guide line for.
+    fn fmt_children(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    where
+        T: fmt::Display,
+    {
+        let mut guides: Vec<bool> = Vec::new();
+        let mut pending: Vec<(&Node<T>, usize, bool)> = Vec::new();
+        let mut parent = self;
+        let mut depth = 0;
+        loop {
+            // Push the right child first so the left one is written first; the last child gets the corner
+            if let Some(right) = parent.right.as_deref() {
+                pending.push((right, depth, true));
+            }
+            if let Some(left) = parent.left.as_deref() {
+                pending.push((left, depth, parent.right.is_none()));
+            }
+            let Some((node, node_depth, is_last)) = pending.pop() else {
+                return Ok(());
+            };
+            guides.truncate(node_depth);
+            f.write_str("\n")?;
+            for &more in &guides {
+                f.write_str(if more { "│   " } else { "    " })?;
+            }
+            write!(f, "{}{}", if is_last { "└── " } else { "├── " }, node.value)?;
+            guides.push(!is_last);
+            parent = node;
+            depth = node_depth + 1;
+        }
+    }
+
+    // Hash a subtree in pre-order, writing a marker for every present and missing child
+    #[cfg(feature = "std")]
+    fn hash_structure<H: Hasher>(link: &Option<Box<Node<T>>>, state: &mut H)
+    where
+        T: Hash,
+    {
+        match link {
+            Some(node) => {
+                1u8.hash(state);
+                node.value.hash(state);
+                Node::hash_structure(&node.left, state);
+                Node::hash_structure(&node.right, state);
+            }
+            None => 0u8.hash(state),
+        }
+    }
+}
+
+// State of a link-reversing walk: the subtree being visited and the chain of reversed ancestors above it.
+// Dropping it, when the walk ends or `f` panics, climbs the chain restoring every link and puts the
+// whole tree back into `link`.
+struct LinkReversal<'a, T> {
+    link: &'a mut Option<Box<Node<T>>>,
+    node: Option<Box<Node<T>>>,
+    up: Option<Box<Node<T>>>,
+}
+
+impl<T> Drop for LinkReversal<'_, T> {
+    fn drop(&mut self) {
+        let Some(mut node) = self.node.take() else {
+            return;
+        };
+        while let Some(mut parent) = self.up.take() {
+            if parent.size == 0 {
+                self.up = parent.right.replace(node);
+                parent.update();
+            } else {
+                self.up = parent.left.replace(node);
+            }
+            node = parent;
+        }
+        *self.link = Some(node);
+    }
+}
+
+// Quote a value as a Graphviz node ID
+fn dot_id<T: fmt::Display>(value: &T) -> String {
+    let label = value.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", label)
+}
+
+/// A binary search tree of unique values, kept in the order decided by `C`: the natural order
+/// of `T` by default, or a comparison closure given to `with_comparator`. Values the comparator
+/// considers equal count as duplicates.
+pub struct BinarySearchTree<T, C = Natural> {
+    root: Option<Box<Node<T>>>,
+    cmp: C,
+}
+
+// Constructors for the natural order
+impl<T: Ord> BinarySearchTree<T> {
+    /// Create a new empty BST
+    pub fn new() -> Self {
+        BinarySearchTree { root: None, cmp: Natural }
+    }
+
+    /// Create a new empty BST. Every node is boxed on its own, so there is no storage to size up
+    /// front and `capacity` is ignored; it exists so code can switch between this and `ArenaBST`.
+    pub fn with_capacity(_capacity: usize) -> Self {
+        Self::new()
+    }
+
+    /// Build a height-balanced BST from a sorted, de-duplicated slice in O(n), cloning each element once.
+    /// The input isn't checked: if it's unsorted or has duplicates the result isn't a valid BST,
+    /// and lookups can miss values that are stored, so sort and de-duplicate the input first.
+    pub fn from_sorted_slice(items: &[T]) -> BinarySearchTree<T>
+    where
+        T: Clone,
+    {
+        BinarySearchTree {
+            root: Node::from_sorted_slice(items),
+            cmp: Natural,
+        }
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering> BinarySearchTree<T, F> {
+    /// Create a new empty BST ordered by `cmp` instead of `Ord`, e.g. to order strings by length.
+    /// `cmp` must be a total order, and values it considers equal are treated as duplicates.
+    pub fn with_comparator(cmp: F) -> Self {
+        BinarySearchTree { root: None, cmp }
+    }
+}
+
+// Implement methods for the BST
+impl<T, C: Comparator<T>> BinarySearchTree<T, C> {
+    /// Insert a value into the BST
+    pub fn insert(&mut self, value: T) -> Result<(), BSTError<T>> {
+        Node::insert(&mut self.root, value, &self.cmp)
+    }
+
+    /// Insert a value as `insert` does, adding the number of comparator calls it made to `counter`,
+    /// e.g. to measure the average insertion cost of different build orders
+    pub fn insert_counting(&mut self, value: T, counter: &mut usize) -> Result<(), BSTError<T>> {
+        let calls = Cell::new(0);
+        let counting = |a: &T, b: &T| {
+            calls.set(calls.get() + 1);
+            self.cmp.compare(a, b)
+        };
+        let result = Node::insert(&mut self.root, value, &counting);
+        *counter += calls.get();
+        result
+    }
+
+    /// Insert a value if it isn't already present, returning whether it was inserted.
+    /// A duplicate is detected during the same descent and simply dropped.
+    pub fn insert_if_absent(&mut self, value: T) -> bool {
+        self.insert(value).is_ok()
+    }
+
+    /// Insert every value, skipping duplicates as `Extend` does, and report how many of each there were
+    pub fn insert_all<I: IntoIterator<Item = T>>(&mut self, iter: I) -> InsertReport {
+        let mut report = InsertReport::default();
+        for value in iter {
+            if self.insert_if_absent(value) {
+                report.inserted += 1;
+            } else {
+                report.duplicates += 1;
+            }
+        }
+        report
+    }
+
+    /// Insert a value, replacing and returning the stored value equal to it if there is one,
+    /// like `HashMap::insert`. This matters when equality doesn't mean identity.
+    pub fn insert_or_replace(&mut self, value: T) -> Option<T> {
+        Node::insert_or_replace(&mut self.root, value, &self.cmp)
+    }
+
+    /// Get the stored value equal to `value`, inserting `value` first if there is none.
+    /// An equal value already stored is kept, and `value` is dropped.
+    pub fn get_or_insert(&mut self, value: T) -> &T {
+        let (path, found) = Node::path_to(self.root.as_deref(), &value, &self.cmp);
+        Node::get_or_fill(&mut self.root, &path, found, || value)
+    }
+
+    /// Get the stored value equal to `key`, inserting `f()` first if there is none, so the value
+    /// is only built when it's missing. Panics if `f()` isn't equal to `key`.
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, key: &T, f: F) -> &T {
+        let (path, found) = Node::path_to(self.root.as_deref(), key, &self.cmp);
+        let cmp = &self.cmp;
+        Node::get_or_fill(&mut self.root, &path, found, || {
+            let value = f();
+            assert!(cmp.compare(&value, key).is_eq(), "get_or_insert_with: f() must return a value equal to the key");
+            value
+        })
+    }
+
+    /// Insert a value and return a reference to it, like `HashMap::try_insert`. If an equal value
+    /// is already stored, nothing changes and the error pairs that stored value with the rejected
+    /// `value`, so neither is lost.
+    #[allow(clippy::type_complexity)]
+    pub fn try_insert(&mut self, value: T) -> Result<&T, (&T, T)> {
+        let (path, found) = Node::path_to(self.root.as_deref(), &value, &self.cmp);
+        if found {
+            return Err((&Node::node_at(&self.root, &path).value, value));
+        }
+        Ok(Node::attach_at(&mut self.root, &path, value))
+    }
+
+    /// Look up the stored value equal to `value` in a single descent, returning an entry that can
+    /// read it, or insert `value` if there is none, without searching again
+    pub fn entry(&mut self, value: T) -> Entry<'_, T, C> {
+        let (path, found) = Node::path_to(self.root.as_deref(), &value, &self.cmp);
+        if found {
+            Entry::Occupied(OccupiedEntry { tree: self, path })
+        } else {
+            Entry::Vacant(VacantEntry { tree: self, path, value })
+        }
+    }
+
+    /// Search for a value in the BST
+    pub fn search(&self, value: T) -> bool {
+        self.contains(&value)
+    }
+
+    /// Check whether a value is in the BST without taking ownership of the query
+    pub fn contains(&self, value: &T) -> bool {
+        Node::find(self.root.as_deref(), value, &self.cmp).is_some()
+    }
+
+    /// Check whether every value in `values` is stored, stopping at the first missing one
+    pub fn contains_all(&self, values: &[T]) -> bool {
+        values.iter().all(|value| self.contains(value))
+    }
+
+    /// Check whether any value in `values` is stored, stopping at the first one found
+    pub fn contains_any(&self, values: &[T]) -> bool {
+        values.iter().any(|value| self.contains(value))
+    }
+
+    /// Get a reference to the stored value equal to `value`. This matters when equality
+    /// doesn't mean identity, e.g. for values ordered by only some of their fields.
+    pub fn get(&self, value: &T) -> Option<&T> {
+        Node::find(self.root.as_deref(), value, &self.cmp).map(|node| &node.value)
+    }
+
+    /// Delete a value from the BST, handing it back in `ValueNotFound` if it isn't there
+    pub fn delete(&mut self, value: T) -> Result<(), BSTError<T>> {
+        match self.remove(&value) {
+            Some(_) => Ok(()),
+            None => Err(BSTError::ValueNotFound(value)),
+        }
+    }
+
+    /// Remove the value equal to `value` from the BST and return the stored value,
+    /// or `None` if there is none
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        Node::remove(&mut self.root, value, &self.cmp)
+    }
+
+    /// Replace `old` with `new`, moving it to wherever `new` belongs in the order. Fails with
+    /// `ValueNotFound` if `old` isn't stored or `DuplicateValue` if another value equal to `new`
+    /// is, leaving the BST untouched in either case.
+    pub fn replace_value(&mut self, old: &T, new: T) -> Result<(), BSTError<T>>
+    where
+        T: Clone,
+    {
+        if !self.contains(old) {
+            return Err(BSTError::ValueNotFound(old.clone()));
+        }
+        if self.cmp.compare(old, &new).is_ne() && self.contains(&new) {
+            return Err(BSTError::DuplicateValue(new));
+        }
+        self.remove(old);
+        self.insert(new)
+    }
+
+    /// Remove and return the smallest value, or `None` if the BST is empty
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.root.as_ref()?;
+        Some(Node::take_min(&mut self.root).value)
+    }
+
+    /// Remove and return the largest value, or `None` if the BST is empty
+    pub fn pop_max(&mut self) -> Option<T> {
+        self.root.as_ref()?;
+        Some(Node::take_max(&mut self.root).value)
+    }
+
+    /// Keep only the values for which `f` returns `true`, visiting them in sorted order.
+    /// The survivors are moved into a freshly built balanced BST in O(n).
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let kept: Vec<T> = IntoIter::new(self.root.take()).filter(|value| f(value)).collect();
+        self.root = Node::from_sorted_iter(kept.len(), &mut kept.into_iter());
+    }
+
+    /// Remove the values from `low` to `high` inclusive and return them in sorted order. The
+    /// remaining values are moved into a freshly built balanced BST in O(n); an empty or inverted
+    /// range leaves the BST untouched.
+    pub fn drain_range(&mut self, low: &T, high: &T) -> Vec<T> {
+        let drained = self.count_in_range(low, high);
+        if drained == 0 {
+            return Vec::new();
+        }
+        let below = self.rank(low);
+        let kept = self.len() - drained;
+        let mut values = IntoIter::new(self.root.take());
+        let lower: Vec<T> = values.by_ref().take(below).collect();
+        let result: Vec<T> = values.by_ref().take(drained).collect();
+        self.root = Node::from_sorted_iter(kept, &mut lower.into_iter().chain(values));
+        result
+    }
+
+    /// Remove every value less than `threshold`, in O(height) plus the cost of freeing them, by
+    /// cutting off whole subtrees that lie below it
+    pub fn prune_below(&mut self, threshold: &T) {
+        Node::prune(&mut self.root, threshold, Ordering::Less, &self.cmp);
+    }
+
+    /// Remove every value greater than `threshold`, in O(height) plus the cost of freeing them, by
+    /// cutting off whole subtrees that lie above it
+    pub fn prune_above(&mut self, threshold: &T) {
+        Node::prune(&mut self.root, threshold, Ordering::Greater, &self.cmp);
+    }
+
+    /// Does nothing, since every node gets its own allocation on insert; it mirrors
+    /// `ArenaBST::reserve` so code can switch between the two
+    pub fn reserve(&mut self, _additional: usize) {}
+
+    /// Remove every value, leaving an empty BST that can be reused
+    pub fn clear(&mut self) {
+        Node::dismantle(self.root.take());
+    }
+
+    /// Insert a batch of values sorted in ascending order by merging it with the stored values and
+    /// rebuilding a balanced BST, in O(n + m). As with `insert`, a value that's already stored or
+    /// repeated in the batch is a `DuplicateValue`; a batch out of order is `OutOfRange`. Either way
+    /// the BST is left unchanged.
+    pub fn insert_sorted(&mut self, sorted: &[T]) -> Result<(), BSTError<T>>
+    where
+        T: Clone,
+    {
+        for pair in sorted.windows(2) {
+            match self.cmp.compare(&pair[0], &pair[1]) {
+                Ordering::Less => {}
+                Ordering::Equal => return Err(BSTError::DuplicateValue(pair[1].clone())),
+                Ordering::Greater => return Err(BSTError::OutOfRange),
+            }
+        }
+        let mut stored = self.iter().peekable();
+        for value in sorted {
+            while stored.next_if(|existing| self.cmp.compare(existing, value).is_lt()).is_some() {}
+            if stored.peek().is_some_and(|existing| self.cmp.compare(existing, value).is_eq()) {
+                return Err(BSTError::DuplicateValue(value.clone()));
+            }
+        }
+        let len = self.len() + sorted.len();
+        let cmp = &self.cmp;
+        let mut ours = IntoIter::new(self.root.take()).peekable();
+        let mut theirs = sorted.iter().cloned().peekable();
+        let mut merged = core::iter::from_fn(|| match (ours.peek(), theirs.peek()) {
+            (Some(a), Some(b)) if cmp.compare(a, b).is_lt() => ours.next(),
+            (Some(_), None) => ours.next(),
+            _ => theirs.next(),
+        });
+        self.root = Node::from_sorted_iter(len, &mut merged);
+        Ok(())
+    }
+
+    /// Move every value of `other` into this BST, leaving `other` empty, by merging the two sorted
+    /// sequences and rebuilding a balanced BST in O(n + m). A value already stored here is kept and
+    /// the incoming copy dropped; the report counts those as duplicates.
+    pub fn append(&mut self, other: &mut Self) -> InsertReport {
+        let mut report = InsertReport::default();
+        let mut ours = IntoIter::new(self.root.take()).peekable();
+        let mut theirs = IntoIter::new(other.root.take()).peekable();
+        let mut merged = Vec::new();
+        loop {
+            let ordering = match (ours.peek(), theirs.peek()) {
+                (Some(a), Some(b)) => self.cmp.compare(a, b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => merged.extend(ours.next()),
+                Ordering::Greater => {
+                    report.inserted += 1;
+                    merged.extend(theirs.next());
+                }
+                Ordering::Equal => {
+                    report.duplicates += 1;
+                    theirs.next();
+                }
+            }
+        }
+        self.root = Node::from_sorted_iter(merged.len(), &mut merged.into_iter());
+        report
+    }
+
+    /// Replay a diff from `content_diff`: insert every `added` value and delete every `removed` value.
+    /// An added value that already exists or a removed value that is missing means the diff doesn't
+    /// match this tree; the changes made so far are rolled back (contents, not shape) and the error returned.
+    pub fn apply_diff(&mut self, added: &[T], removed: &[T]) -> Result<(), BSTError<T>>
+    where
+        T: Clone,
+    {
+        for (applied, value) in added.iter().enumerate() {
+            if let Err(e) = self.insert(value.clone()) {
+                self.undo_diff(&added[..applied], &[]);
+                return Err(e);
+            }
+        }
+        for (applied, value) in removed.iter().enumerate() {
+            if let Err(e) = self.delete(value.clone()) {
+                self.undo_diff(added, &removed[..applied]);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    // Revert a partially applied diff, restoring deletions before undoing insertions
+    fn undo_diff(&mut self, inserted: &[T], deleted: &[T])
+    where
+        T: Clone,
+    {
+        for value in deleted.iter().rev() {
+            let _ = self.insert(value.clone());
+        }
+        for value in inserted.iter().rev() {
+            let _ = self.delete(value.clone());
+        }
+    }
+
+    /// Find the minimum value in the BST
+    pub fn find_min(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| root.find_min())
+    }
+
+    /// Find the maximum value in the BST
+    pub fn find_max(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| root.find_max())
+    }
+
+    /// Find the value with the smallest key `f` gives it, for extremes along some other dimension
+    /// than the BST's order, in O(n). On a tie the first value in sorted order wins.
+    pub fn min_by_key<B: Ord, F: Fn(&T) -> B>(&self, f: F) -> Option<&T> {
+        self.iter().min_by_key(|value| f(value))
+    }
+
+    /// Find the value with the largest key `f` gives it, for extremes along some other dimension
+    /// than the BST's order, in O(n). On a tie the first value in sorted order wins.
+    pub fn max_by_key<B: Ord, F: Fn(&T) -> B>(&self, f: F) -> Option<&T> {
+        // `Iterator::max_by_key` keeps the last of equal maxima, so walk from the largest value down
+        self.iter().rev().max_by_key(|value| f(value))
+    }
+
+    /// Find both the minimum and maximum values, or `None` if the BST is empty.
+    /// A single-value BST returns that value for both.
+    pub fn min_max(&self) -> Option<(&T, &T)> {
+        self.root.as_ref().map(|root| (root.find_min(), root.find_max()))
+    }
+
+    /// Look at the value `pop_min` would remove, without removing it
+    pub fn peek_min(&self) -> Option<&T> {
+        self.find_min()
+    }
+
+    /// Look at the value `pop_max` would remove, without removing it
+    pub fn peek_max(&self) -> Option<&T> {
+        self.find_max()
+    }
+
+    /// The smallest value, as for an ordered set; the same as `find_min`
+    pub fn first(&self) -> Option<&T> {
+        self.find_min()
+    }
+
+    /// The largest value, as for an ordered set; the same as `find_max`
+    pub fn last(&self) -> Option<&T> {
+        self.find_max()
+    }
+
+    /// The `n`-th smallest value (0-indexed), as for an ordered set; the same as `select`
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        self.select(n)
+    }
+
+    /// Find the largest value less than or equal to `value`, in O(height)
+    pub fn floor(&self, value: &T) -> Option<&T> {
+        let mut best = None;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            match self.cmp.compare(value, &node.value) {
+                Ordering::Less => current = node.left.as_deref(),
+                Ordering::Greater => {
+                    best = Some(&node.value);
+                    current = node.right.as_deref();
+                }
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+        best
+    }
+
+    /// Find the smallest value greater than or equal to `value`, in O(height)
+    pub fn ceiling(&self, value: &T) -> Option<&T> {
+        let mut best = None;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            match self.cmp.compare(value, &node.value) {
+                Ordering::Less => {
+                    best = Some(&node.value);
+                    current = node.left.as_deref();
+                }
+                Ordering::Greater => current = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+        best
+    }
+
+    /// Find the stored value nearest to `value` by the distance `dist` returns, in O(height).
+    /// The nearest value is always the floor or the ceiling; on a tie the smaller one, the floor, wins.
+    pub fn closest_by<O: PartialOrd, F: Fn(&T, &T) -> O>(&self, value: &T, dist: F) -> Option<&T> {
+        match (self.floor(value), self.ceiling(value)) {
+            (Some(below), Some(above)) if dist(value, above) < dist(value, below) => Some(above),
+            (Some(below), _) => Some(below),
+            (None, above) => above,
+        }
+    }
+
+    /// Find the stored number nearest to `value` by absolute difference, in O(height).
+    /// On a tie the smaller number wins, e.g. 10 rather than 12 for 11.
+    pub fn closest(&self, value: &T) -> Option<&T>
+    where
+        T: Copy + PartialOrd + core::ops::Sub<Output = T>,
+    {
+        self.closest_by(value, |a, b| if a < b { *b - *a } else { *a - *b })
+    }
+
+    /// Find the next larger value after `value`, whether or not `value` is in the BST, in O(height)
+    pub fn successor(&self, value: &T) -> Option<&T> {
+        let mut best = None;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            if self.cmp.compare(value, &node.value).is_lt() {
+                best = Some(&node.value);
+                current = node.left.as_deref();
+            } else {
+                current = node.right.as_deref();
+            }
+        }
+        best
+    }
+
+    /// Find the next smaller value before `value`, whether or not `value` is in the BST, in O(height)
+    pub fn predecessor(&self, value: &T) -> Option<&T> {
+        let mut best = None;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            if self.cmp.compare(value, &node.value).is_gt() {
+                best = Some(&node.value);
+                current = node.right.as_deref();
+            } else {
+                current = node.left.as_deref();
+            }
+        }
+        best
+    }
+
+    /// Find the largest value strictly less than `value`, in O(height). Unlike `floor`, an equal
+    /// stored value is skipped. Same as `predecessor`, under the name Java's `TreeSet` uses.
+    pub fn lower(&self, value: &T) -> Option<&T> {
+        self.predecessor(value)
+    }
+
+    /// Find the smallest value strictly greater than `value`, in O(height). Unlike `ceiling`, an
+    /// equal stored value is skipped. Same as `successor`, under the name Java's `TreeSet` uses.
+    pub fn higher(&self, value: &T) -> Option<&T> {
+        self.successor(value)
+    }
+
+    /// Find the deepest node that has both `a` and `b` in its subtree (a node counts as its own
+    /// descendant), in O(height). Returns `None` unless both values are in the BST.
+    pub fn lowest_common_ancestor(&self, a: &T, b: &T) -> Option<&T> {
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            current = match (self.cmp.compare(a, &node.value), self.cmp.compare(b, &node.value)) {
+                (Ordering::Less, Ordering::Less) => node.left.as_deref(),
+                (Ordering::Greater, Ordering::Greater) => node.right.as_deref(),
+                _ => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    /// Look for `value` and count the nodes compared against on the way, which is its depth plus
+    /// one on a hit, to measure lookup cost e.g. before and after balancing. Doesn't allocate.
+    pub fn search_profiled(&self, value: &T) -> (bool, usize) {
+        let mut comparisons = 0;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            comparisons += 1;
+            current = match self.cmp.compare(value, &node.value) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return (true, comparisons),
+            };
+        }
+        (false, comparisons)
+    }
+
+    /// Number of edges from the root down to the node holding `value` (the root is at depth 0),
+    /// or `None` if it isn't in the BST, in O(height)
+    pub fn depth_of(&self, value: &T) -> Option<usize> {
+        let mut depth = 0;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            current = match self.cmp.compare(value, &node.value) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(depth),
+            };
+            depth += 1;
+        }
+        None
+    }
+
+    /// The values passed descending from the root to the node holding `value`, both included,
+    /// or `None` if it isn't in the BST, in O(height)
+    pub fn path_to(&self, value: &T) -> Option<Vec<&T>> {
+        let mut path = Vec::new();
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            path.push(&node.value);
+            current = match self.cmp.compare(value, &node.value) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(path),
+            };
+        }
+        None
+    }
+
+    /// Check whether the key ranges of two BSTs don't interleave, in O(height).
+    /// An empty tree is disjoint from every tree.
+    pub fn ranges_disjoint(&self, other: &Self) -> bool {
+        match (self.find_min(), self.find_max(), other.find_min(), other.find_max()) {
+            (Some(min), Some(max), Some(other_min), Some(other_max)) => {
+                self.cmp.compare(max, other_min).is_lt() || self.cmp.compare(other_max, min).is_lt()
+            }
+            _ => true,
+        }
+    }
+
+    /// Append a BST whose values are all greater than this tree's, in O(height) instead of
+    /// re-inserting every value. The smallest value of `other` joins the two trees where their
+    /// heights meet, attaching the shorter one as a subtree of the taller, and the nodes above
+    /// the join are rebalanced with rotations, so two height-balanced trees give a
+    /// height-balanced result. Returns `OverlappingRanges` unless every value in `other` is
+    /// strictly greater than every value in `self`.
+    pub fn concat(&mut self, mut other: Self) -> Result<(), BSTError<T>> {
+        if let (Some(max), Some(other_min)) = (self.find_max(), other.find_min()) {
+            if self.cmp.compare(max, other_min).is_ge() {
+                return Err(BSTError::OverlappingRanges);
+            }
+        }
+        if other.root.is_none() {
+            return Ok(());
+        }
+        let pivot = Node::take_min(&mut other.root);
+        self.root = Node::join(self.root.take(), pivot, other.root.take());
+        Ok(())
+    }
+
+    /// Combine two BSTs whose key ranges don't interleave, in O(height) by joining the higher tree
+    /// onto the lower one and rebalancing, as `concat` does; the arguments can come in either order.
+    /// Returns `OverlappingRanges` if the ranges interleave.
+    pub fn merge(self, other: Self) -> Result<Self, BSTError<T>> {
+        if !self.ranges_disjoint(&other) {
+            return Err(BSTError::OverlappingRanges);
+        }
+        let other_below = match (other.find_max(), self.find_min()) {
+            (Some(other_max), Some(min)) => self.cmp.compare(other_max, min).is_lt(),
+            _ => false,
+        };
+        let (mut low, high) = if other_below { (other, self) } else { (self, other) };
+        low.concat(high)?;
+        Ok(low)
+    }
+
+    /// Partition the BST into the values less than `key` and the values greater than or equal
+    /// to it, moving the values into two balanced BSTs in O(n)
+    pub fn split(mut self, key: &T) -> (Self, Self)
+    where
+        C: Clone,
+    {
+        let below = self.rank(key);
+        let at_or_above = self.len() - below;
+        let mut values = IntoIter::new(self.root.take());
+        let lower = BinarySearchTree {
+            root: Node::from_sorted_iter(below, &mut values),
+            cmp: self.cmp.clone(),
+        };
+        let upper = BinarySearchTree {
+            root: Node::from_sorted_iter(at_or_above, &mut values),
+            cmp: self.cmp.clone(),
+        };
+        (lower, upper)
+    }
+
+    /// Replace the subtree rooted at the node holding `at` with `new_subtree` and return the
+    /// detached subtree. Every value of `new_subtree` must fit the slot it moves into, i.e. lie
+    /// strictly between the closest ancestors bounding that position; otherwise `OutOfRange`
+    /// is returned and the tree is left unchanged.
+    pub fn replace_subtree(&mut self, at: &T, mut new_subtree: Self) -> Result<Self, BSTError<T>>
+    where
+        T: Clone,
+        C: Clone,
+    {
+        let mut lower = None;
+        let mut upper = None;
+        let mut current = self.root.as_deref();
+        loop {
+            let node = current.ok_or_else(|| BSTError::ValueNotFound(at.clone()))?;
+            match self.cmp.compare(at, &node.value) {
+                Ordering::Less => {
+                    upper = Some(&node.value);
+                    current = node.left.as_deref();
+                }
+                Ordering::Greater => {
+                    lower = Some(&node.value);
+                    current = node.right.as_deref();
+                }
+                Ordering::Equal => break,
+            }
+        }
+        let fits_lower = match (lower, new_subtree.find_min()) {
+            (Some(lower), Some(min)) => self.cmp.compare(min, lower).is_gt(),
+            _ => true,
+        };
+        let fits_upper = match (upper, new_subtree.find_max()) {
+            (Some(upper), Some(max)) => self.cmp.compare(max, upper).is_lt(),
+            _ => true,
+        };
+        if !fits_lower || !fits_upper {
+            return Err(BSTError::OutOfRange);
+        }
+        let (path, _) = Node::path_to(self.root.as_deref(), at, &self.cmp);
+        let link = Node::link_at(&mut self.root, &path);
+        let old_root = core::mem::replace(link, new_subtree.root.take());
+        Node::fix_path(&mut self.root, &path);
+        Ok(BinarySearchTree {
+            root: old_root,
+            cmp: self.cmp.clone(),
+        })
+    }
+
+    /// Iterate over the values in sorted order without cloning or allocating a `Vec`
+    pub fn iter(&self) -> InOrderIter<'_, T> {
+        InOrderIter::new(self.root.as_deref())
+    }
+
+    /// Consume the BST and collect its values in sorted order, moving them out instead of cloning
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.len());
+        result.extend(self);
+        result
+    }
+
+    /// Consume the BST and return its `k` largest values in descending order, or all of them if
+    /// there are fewer. A reverse in-order walk moves the values out and stops after `k`, so only
+    /// O(k + height) nodes are visited before the rest are freed.
+    pub fn into_top_k(mut self, k: usize) -> Vec<T> {
+        let mut result = Vec::with_capacity(k.min(self.len()));
+        let mut stack: Vec<Box<Node<T>>> = Vec::new();
+        let mut link = self.root.take();
+        while result.len() < k {
+            while let Some(mut node) = link {
+                link = node.right.take();
+                stack.push(node);
+            }
+            let Some(mut node) = stack.pop() else {
+                break;
+            };
+            link = node.left.take();
+            result.push(node.value);
+        }
+        Node::dismantle(link);
+        for node in stack {
+            Node::dismantle(Some(node));
+        }
+        result
+    }
+
+    /// Freeze the BST into a read-only handle that can be cloned cheaply and shared across threads
+    pub fn into_shared(self) -> SharedBST<T, C> {
+        SharedBST::new(self)
+    }
+
+    /// Iterate over the values from largest to smallest, lazily, e.g. `iter_rev().take(3)` for the three largest
+    pub fn iter_rev(&self) -> RevInOrderIter<'_, T> {
+        RevInOrderIter::new(self.root.as_deref())
+    }
+
+    /// Call `f` on every value in sorted order, without collecting them or allocating
+    pub fn for_each_in_order<F: FnMut(&T)>(&self, mut f: F) {
+        self.fold_in_order((), |(), value| f(value));
+    }
+
+    /// Call `f` on a mutable reference to every value in sorted order, to update the parts of the
+    /// values that don't take part in the comparison. `f` must not change where a value sorts, or
+    /// lookups may miss it; debug builds check the BST is still valid afterwards.
+    pub fn for_each_mut<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        let mut stack: Vec<(&mut T, &mut Option<Box<Node<T>>>)> = Vec::new();
+        let mut link = &mut self.root;
+        loop {
+            while let Some(node) = link {
+                let Node { value, left, right, .. } = &mut **node;
+                stack.push((value, right));
+                link = left;
+            }
+            let Some((value, right)) = stack.pop() else {
+                break;
+            };
+            f(value);
+            link = right;
+        }
+        debug_assert!(self.is_valid_bst(), "for_each_mut: f changed the order of the values");
+    }
+
+    /// Call `f` on every value in sorted order using O(1) extra space instead of a stack as deep as
+    /// the BST, by temporarily reversing child links as it descends. The shape and caches are fully
+    /// restored when it returns, and also if `f` panics.
+    pub fn for_each_in_order_in_place<F: FnMut(&T)>(&mut self, mut f: F) {
+        Node::for_each_reversing_links(&mut self.root, &mut f);
+    }
+
+    /// Same as `for_each_in_order_in_place`, under the name of the constant-space traversal it stands
+    /// in for; it reverses links instead of threading them as Morris does, which boxed nodes can't.
+    pub fn morris_for_each<F: FnMut(&T)>(&mut self, f: F) {
+        self.for_each_in_order_in_place(f);
+    }
+
+    /// Combine the values in sorted order into one result, starting from `init`, without allocating,
+    /// e.g. `fold_in_order(0, |sum, v| sum + v)`
+    pub fn fold_in_order<B, F: FnMut(B, &T) -> B>(&self, init: B, mut f: F) -> B {
+        match self.root {
+            Some(ref root) => root.fold_in_order(init, &mut f),
+            None => init,
+        }
+    }
+
+    /// In-order traversal
+    pub fn in_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.in_order_traversal(&mut result);
+        }
+        result
+    }
+
+    /// Values between two bounds in sorted order; each bound can be inclusive, exclusive or unbounded,
+    /// e.g. `(Bound::Included(low), Bound::Excluded(high))` for the half-open range `[low, high)`
+    pub fn range_with_bounds(&self, low: Bound<&T>, high: Bound<&T>) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.range_traversal(low, high, &mut result, &self.cmp);
+        }
+        result
+    }
+
+    /// Values from `low` to `high` inclusive in sorted order; an inverted range (`low > high`) is empty
+    pub fn range(&self, low: &T, high: &T) -> Vec<T>
+    where
+        T: Clone,
+    {
+        if self.cmp.compare(low, high).is_gt() {
+            return Vec::new();
+        }
+        self.range_with_bounds(Bound::Included(low), Bound::Included(high))
+    }
+
+    /// Count the values from `low` to `high` inclusive without collecting them; an inverted range counts 0
+    pub fn count_in_range(&self, low: &T, high: &T) -> usize {
+        if self.cmp.compare(low, high).is_gt() {
+            return 0;
+        }
+        self.root.as_ref().map_or(0, |root| root.count_in_range(low, high, &self.cmp))
+    }
+
+    /// Values strictly between `low` and `high` in sorted order
+    pub fn range_exclusive(&self, low: &T, high: &T) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.range_with_bounds(Bound::Excluded(low), Bound::Excluded(high))
+    }
+
+    /// In-order traversal pairing each value with its depth (root = 0), e.g. to indent a printout
+    pub fn in_order_with_depth(&self) -> Vec<(T, usize)>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.in_order_with_depth(&mut result);
+        }
+        result
+    }
+
+    /// Pre-order traversal
+    pub fn pre_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.pre_order_traversal(&mut result);
+        }
+        result
+    }
+
+    /// Post-order traversal
+    pub fn post_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.post_order_traversal(&mut result);
+        }
+        result
+    }
+
+    /// Sketch the left profile: starting at the root, take the left child when there is one and
+    /// fall back to the right child otherwise, recording one value per level.
+    /// Unlike a left-side view, which reports the first node of every level even when it hangs off
+    /// another branch, this follows a single root-to-leaf path and may stop before the deepest level.
+    pub fn level_representatives(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            result.push(node.value.clone());
+            current = node.left.as_deref().or(node.right.as_deref());
+        }
+        result
+    }
+
+    /// Group the in-order sequence into runs of consecutive values sharing a derived key.
+    /// Only adjacent values are grouped; since the in-order sequence is sorted, this
+    /// collects every equal key whenever the key function preserves the ordering (e.g. `v / 10`).
+    pub fn group_by<K2: PartialEq, F: Fn(&T) -> K2>(&self, key_fn: F) -> Vec<(K2, Vec<T>)>
+    where
+        T: Clone,
+    {
+        let mut groups: Vec<(K2, Vec<T>)> = Vec::new();
+        for value in self.in_order_traversal() {
+            let key = key_fn(&value);
+            match groups.last_mut() {
+                Some((last_key, group)) if *last_key == key => group.push(value),
+                _ => groups.push((key, vec![value])),
+            }
+        }
+        groups
+    }
+
+    /// Apply `f` to every value and collect the results into a new balanced BST in their natural
+    /// order, since the mapping may reorder them. When several values map to equal results, the
+    /// one from the smallest source value is kept, as repeated `insert`s in sorted order would.
+    pub fn map<U: Ord, F: Fn(&T) -> U>(&self, f: F) -> BinarySearchTree<U> {
+        let mut mapped: Vec<U> = self.iter().map(f).collect();
+        mapped.sort();
+        mapped.dedup();
+        BinarySearchTree {
+            root: Node::from_sorted_iter(mapped.len(), &mut mapped.into_iter()),
+            cmp: Natural,
+        }
+    }
+
+    /// Values in either BST, as a new balanced BST; for values in both, this tree's copy is kept
+    pub fn union(&self, other: &Self) -> Self
+    where
+        T: Clone,
+        C: Clone,
+    {
+        self.combine(other, true, true, true)
+    }
+
+    /// Values in both BSTs, as a new balanced BST holding this tree's copies
+    pub fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+        C: Clone,
+    {
+        self.combine(other, false, true, false)
+    }
+
+    /// Values in this BST but not in `other`, as a new balanced BST
+    pub fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+        C: Clone,
+    {
+        self.combine(other, true, false, false)
+    }
+
+    /// Values in exactly one of the two BSTs, as a new balanced BST
+    pub fn symmetric_difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+        C: Clone,
+    {
+        self.combine(other, true, false, true)
+    }
+
+    /// Union, intersection, difference and symmetric difference at once, from a single merge walk
+    /// over both BSTs instead of one per operation. Shared values come from this tree.
+    pub fn set_ops(&self, other: &Self) -> SetOps<T, C>
+    where
+        T: Clone,
+        C: Clone,
+    {
+        let mut union = Vec::new();
+        let mut intersection = Vec::new();
+        let mut difference = Vec::new();
+        let mut symmetric_difference = Vec::new();
+        let mut ours = self.iter().peekable();
+        let mut theirs = other.iter().peekable();
+        loop {
+            let ordering = match (ours.peek(), theirs.peek()) {
+                (Some(a), Some(b)) => self.cmp.compare(a, b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => {
+                    let value = ours.next().unwrap();
+                    union.push(value.clone());
+                    difference.push(value.clone());
+                    symmetric_difference.push(value.clone());
+                }
+                Ordering::Greater => {
+                    let value = theirs.next().unwrap();
+                    union.push(value.clone());
+                    symmetric_difference.push(value.clone());
+                }
+                Ordering::Equal => {
+                    let value = ours.next().unwrap();
+                    theirs.next();
+                    union.push(value.clone());
+                    intersection.push(value.clone());
+                }
+            }
+        }
+        let build = |values: Vec<T>| BinarySearchTree {
+            root: Node::from_sorted_iter(values.len(), &mut values.into_iter()),
+            cmp: self.cmp.clone(),
+        };
+        SetOps {
+            union: build(union),
+            intersection: build(intersection),
+            difference: build(difference),
+            symmetric_difference: build(symmetric_difference),
+        }
+    }
+
+    /// Check whether the two BSTs have no value in common, with a merge walk that stops at the
+    /// first shared value. An empty BST is disjoint from every BST.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let mut ours = self.iter().peekable();
+        let mut theirs = other.iter().peekable();
+        while let (Some(a), Some(b)) = (ours.peek(), theirs.peek()) {
+            match self.cmp.compare(a, b) {
+                Ordering::Less => ours.next(),
+                Ordering::Greater => theirs.next(),
+                Ordering::Equal => return false,
+            };
+        }
+        true
+    }
+
+    /// Check whether every value of this BST is also in `other`, with a merge walk that stops at
+    /// the first missing value. An empty BST is a subset of every BST.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        if self.len() > other.len() {
+            return false;
+        }
+        let mut theirs = other.iter().peekable();
+        for value in self.iter() {
+            while theirs.next_if(|candidate| self.cmp.compare(candidate, value).is_lt()).is_some() {}
+            if theirs.next_if(|candidate| self.cmp.compare(candidate, value).is_eq()).is_none() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The smallest and largest values from `low` to `high` inclusive, or `None` if no value lies
+    /// in that range, in O(height) from the ceiling of `low` and the floor of `high`
+    pub fn range_bounds(&self, low: &T, high: &T) -> Option<(&T, &T)> {
+        let first = self.ceiling(low)?;
+        let last = self.floor(high)?;
+        self.cmp.compare(first, last).is_le().then_some((first, last))
+    }
+
+    /// Check whether any value lies from `low` to `high` inclusive, in O(height)
+    pub fn intersects_range(&self, low: &T, high: &T) -> bool {
+        self.ceiling(low).is_some_and(|value| self.cmp.compare(value, high).is_le())
+    }
+
+    // Merge-walk the sorted values of both BSTs in O(n + m), keeping the values only in `self`,
+    // in both, or only in `other` as selected, and build a balanced BST from them
+    fn combine(&self, other: &Self, keep_ours: bool, keep_both: bool, keep_theirs: bool) -> Self
+    where
+        T: Clone,
+        C: Clone,
+    {
+        let mut kept = Vec::new();
+        let mut ours = self.iter().peekable();
+        let mut theirs = other.iter().peekable();
+        while let (Some(a), Some(b)) = (ours.peek(), theirs.peek()) {
+            match self.cmp.compare(a, b) {
+                Ordering::Less => kept.extend(ours.next().filter(|_| keep_ours).cloned()),
+                Ordering::Greater => kept.extend(theirs.next().filter(|_| keep_theirs).cloned()),
+                Ordering::Equal => {
+                    kept.extend(ours.next().filter(|_| keep_both).cloned());
+                    theirs.next();
+                }
+            }
+        }
+        if keep_ours {
+            kept.extend(ours.cloned());
+        }
+        if keep_theirs {
+            kept.extend(theirs.cloned());
+        }
+        BinarySearchTree {
+            root: Node::from_sorted_iter(kept.len(), &mut kept.into_iter()),
+            cmp: self.cmp.clone(),
+        }
+    }
+
+    /// Diff the contents of two BSTs with a merge walk over their sorted sequences.
+    /// Returns `(added, removed)`: the values only in `other` and the values only in `self`,
+    /// both in ascending order.
+    pub fn content_diff(&self, other: &Self) -> (Vec<T>, Vec<T>)
+    where
+        T: Clone,
+    {
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        let mut ours = self.in_order_traversal().into_iter().peekable();
+        let mut theirs = other.in_order_traversal().into_iter().peekable();
+        while let (Some(a), Some(b)) = (ours.peek(), theirs.peek()) {
+            match self.cmp.compare(a, b) {
+                Ordering::Less => removed.extend(ours.next()),
+                Ordering::Greater => added.extend(theirs.next()),
+                Ordering::Equal => {
+                    ours.next();
+                    theirs.next();
+                }
+            }
+        }
+        removed.extend(ours);
+        added.extend(theirs);
+        (added, removed)
+    }
+
+    /// Export the BST as an adjacency list: each value (in order) paired with its children's values
+    pub fn to_adjacency_list(&self) -> Vec<(T, Vec<T>)>
+    where
+        T: Clone,
+    {
+        let mut result = Vec::new();
+        if let Some(ref root) = self.root {
+            root.adjacency_traversal(&mut result);
+        }
+        result
+    }
+
+    /// Count the number of nodes in the BST by walking the whole tree.
+    /// `len` reads the same number from the root's cached subtree size; this is kept as a cross-check.
+    pub fn count_nodes(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.count_nodes())
+    }
+
+    /// Number of values in the BST, in O(1)
+    pub fn len(&self) -> usize {
+        Node::size_of(&self.root)
+    }
+
+    /// Check if the BST holds no values, in O(1)
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Find the median value, in O(height). With an even number of values this is the lower of
+    /// the two middle values, since values can't generally be averaged.
+    pub fn median(&self) -> Option<&T> {
+        self.select(self.len().checked_sub(1)? / 2)
+    }
+
+    /// Find the `k`-th smallest value (0-indexed), in O(height) using the cached subtree sizes
+    pub fn select(&self, k: usize) -> Option<&T> {
+        Node::select(self.root.as_deref(), k)
+    }
+
+    /// Find the `k`-th smallest value (1-indexed, so `kth_smallest(1)` is the minimum), in O(height)
+    pub fn kth_smallest(&self, k: usize) -> Option<&T> {
+        self.select(k.checked_sub(1)?)
+    }
+
+    /// Find the `k`-th largest value (1-indexed, so `kth_largest(1)` is the maximum), in O(height)
+    pub fn kth_largest(&self, k: usize) -> Option<&T> {
+        self.select(self.len().checked_sub(k)?)
+    }
+
+    /// Count the values strictly less than `value`, whether or not `value` is in the BST, in O(height)
+    pub fn rank(&self, value: &T) -> usize {
+        Node::rank(self.root.as_deref(), value, &self.cmp)
+    }
+
+    /// Count the values for which `pred` is `false`, like `slice::partition_point`. `pred` must be
+    /// monotone over the sorted order, `false` for a prefix of the values and `true` for the rest,
+    /// e.g. `|value| *value >= threshold`; then this descends one path in O(height) using the cached
+    /// subtree sizes. For any other predicate the count is unspecified; use `iter().filter` instead.
+    pub fn partition_count<F: Fn(&T) -> bool>(&self, pred: F) -> usize {
+        let mut count = 0;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            current = if pred(&node.value) {
+                node.left.as_deref()
+            } else {
+                count += Node::size_of(&node.left) + 1;
+                node.right.as_deref()
+            };
+        }
+        count
+    }
+
+    /// Count the nodes with exactly two children
+    pub fn count_full_nodes(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.count_by_children().0)
+    }
+
+    /// Count the nodes with exactly one child
+    pub fn count_half_nodes(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.count_by_children().1)
+    }
+
+    /// Count the leaves, i.e. the nodes without children
+    pub fn count_leaves(&self) -> usize {
+        self.len() - self.count_internal()
+    }
+
+    /// Count the internal nodes, i.e. the nodes with at least one child
+    pub fn count_internal(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| {
+            let (full, half) = root.count_by_children();
+            full + half
+        })
+    }
+
+    /// Length, in edges, of the longest path between any two nodes, which needn't pass through the root.
+    /// 0 for an empty tree or a single node.
+    pub fn diameter(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.diameter())
+    }
+
+    /// Rebalance the BST in place with the Day–Stout–Warren algorithm, producing a tree of
+    /// minimal height, floor(log2(n)) + 1, with the same values. Runs in O(n) time without
+    /// allocating: right rotations first flatten the tree into a sorted right-leaning vine,
+    /// then passes of left rotations fold the vine back into a balanced tree.
+    pub fn balance(&mut self) {
+        let mut link = &mut self.root;
+        let mut count: usize = 0;
+        while link.is_some() {
+            if link.as_ref().unwrap().left.is_some() {
+                Node::rotate_right(link);
+            } else {
+                count += 1;
+                link = &mut link.as_mut().unwrap().right;
+            }
+        }
+
+        // Size of the largest perfect tree that fits, leaving the remainder as a partial bottom level
+        let mut perfect: usize = 0;
+        while perfect * 2 < count {
+            perfect = perfect * 2 + 1;
+        }
+        self.compress(count - perfect);
+        while perfect > 1 {
+            perfect /= 2;
+            self.compress(perfect);
+        }
+    }
+
+    /// Rebuild the BST as a balanced tree of minimal height, moving the values into freshly allocated
+    /// nodes laid out in sorted order, in O(n). Unlike `balance`, which rotates the existing nodes in
+    /// place, this also reclaims the scattered allocations left behind by many deletes.
+    pub fn rebuild(&mut self) {
+        let len = self.len();
+        self.root = Node::from_sorted_iter(len, &mut IntoIter::new(self.root.take()));
+    }
+
+    // Left-rotate every other node down the right spine, `count` times
+    fn compress(&mut self, count: usize) {
+        let mut link = &mut self.root;
+        for _ in 0..count {
+            Node::rotate_left(link);
+            link = &mut link.as_mut().unwrap().right;
+        }
+    }
+
+    /// Mirror the BST in place, swapping the left and right children of every node, so the
+    /// in-order sequence runs from largest to smallest. The values are then in the reverse of the
+    /// comparator's order: lookups, inserts and deletes give wrong answers until the BST is
+    /// inverted back, and `is_valid_bst` reports `false` for more than one value.
+    pub fn invert(&mut self) {
+        Node::invert(&mut self.root);
+    }
+
+    /// A mirrored copy of the BST, leaving this one untouched; see `invert`
+    pub fn inverted(&self) -> Self
+    where
+        T: Clone,
+        C: Clone,
+    {
+        let mut copy = self.clone();
+        copy.invert();
+        copy
+    }
+
+    /// Gather the node count, height, leaf count, shallowest and deepest leaf depths and balance
+    /// in one traversal, e.g. to spot a degenerate shape whose leaves sit at very different depths
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats {
+            is_balanced: true,
+            ..TreeStats::default()
+        };
+        let mut stack: Vec<(&Node<T>, usize)> = self.root.as_deref().map(|root| (root, 0)).into_iter().collect();
+        while let Some((node, depth)) = stack.pop() {
+            stats.node_count += 1;
+            stats.is_balanced &= node.balance_factor().abs() <= 1;
+            if node.left.is_none() && node.right.is_none() {
+                stats.min_depth = if stats.leaf_count == 0 { depth } else { stats.min_depth.min(depth) };
+                stats.max_depth = stats.max_depth.max(depth);
+                stats.leaf_count += 1;
+            }
+            stack.extend(node.left.as_deref().map(|left| (left, depth + 1)));
+            stack.extend(node.right.as_deref().map(|right| (right, depth + 1)));
+        }
+        if stats.node_count > 0 {
+            stats.height = stats.max_depth + 1;
+        }
+        stats
+    }
+
+    /// Check if the BST is height-balanced: at every node, the heights of the two subtrees differ by at most one
+    pub fn is_balanced(&self) -> bool {
+        self.root.as_ref().is_none_or(|root| root.is_balanced())
+    }
+
+    /// Height of the BST in nodes (0 when empty), in O(1) from the root's cached height
+    pub fn height(&self) -> usize {
+        Node::height_of(&self.root)
+    }
+
+    /// Height in nodes of the subtree rooted at the node holding `value` (a leaf = 1), or `None`
+    /// if `value` isn't stored, in O(height) for the descent
+    pub fn subtree_height(&self, value: &T) -> Option<usize> {
+        Node::find(self.root.as_deref(), value, &self.cmp).map(|node| node.height)
+    }
+
+    /// Height of the left subtree minus the height of the right one at the node holding `value`,
+    /// or `None` if `value` isn't stored. Every node of an AVL-balanced tree has -1, 0 or 1.
+    pub fn balance_factor(&self, value: &T) -> Option<i32> {
+        Node::find(self.root.as_deref(), value, &self.cmp).map(|node| node.balance_factor() as i32)
+    }
+
+    /// The largest balance factor magnitude of any node (0 when empty), so 1 or less means
+    /// the BST is height-balanced
+    pub fn max_balance_factor(&self) -> i32 {
+        let mut max = 0;
+        let mut stack: Vec<&Node<T>> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            max = max.max(node.balance_factor().unsigned_abs());
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+        max as i32
+    }
+
+    /// Number of nodes on the most populated level (0 when empty), walking the BST level by level
+    pub fn max_width(&self) -> usize {
+        self.widest_level().map_or(0, |(_, width)| width)
+    }
+
+    /// The depth of the most populated level along with its number of nodes, taking the
+    /// shallowest level on ties, or `None` if the BST is empty
+    pub fn widest_level(&self) -> Option<(usize, usize)> {
+        let mut widest: Option<(usize, usize)> = None;
+        let mut level: Vec<&Node<T>> = self.root.as_deref().into_iter().collect();
+        let mut depth = 0;
+        while !level.is_empty() {
+            if widest.is_none_or(|(_, width)| level.len() > width) {
+                widest = Some((depth, level.len()));
+            }
+            level = level
+                .iter()
+                .flat_map(|node| node.left.as_deref().into_iter().chain(node.right.as_deref()))
+                .collect();
+            depth += 1;
+        }
+        widest
+    }
+
+    /// Check the BST invariant: walking the tree in order, every value is strictly greater than the
+    /// one before it. Always true for trees built with `insert`; useful to guard `from_sorted_slice`.
+    pub fn is_valid_bst(&self) -> bool {
+        let mut previous = None;
+        for value in self.iter() {
+            if previous.is_some_and(|previous| self.cmp.compare(previous, value).is_ge()) {
+                return false;
+            }
+            previous = Some(value);
+        }
+        true
+    }
+
+    /// Recompute the size and height of every node and compare them with the cached values.
+    /// Returns the values of the nodes whose caches are stale, in post-order, or `Ok(())` if all agree.
+    pub fn verify_caches(&self) -> Result<(), Vec<T>>
+    where
+        T: Clone,
+    {
+        let mut mismatched = Vec::new();
+        Node::verify_caches(&self.root, &mut mismatched);
+        if mismatched.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatched)
+        }
+    }
+
+    /// Remove every node deeper than `max_depth` (root = depth 0) and return the removed values sorted.
+    /// The upper levels that remain are still a valid BST, but may no longer be balanced.
+    pub fn truncate_to_height(&mut self, max_depth: usize) -> Vec<T> {
+        let mut removed = Vec::new();
+        if let Some(ref mut root) = self.root {
+            root.truncate(0, max_depth, &mut removed);
+        }
+        removed
+    }
+
+    // Find the shallowest leaf and the deepest node, each paired with its depth (root = 0).
+    // Ties go to the leftmost candidate.
+    fn depth_extremes(&self) -> Option<((T, usize), (T, usize))>
+    where
+        T: Clone,
+    {
+        let root = self.root.as_ref()?;
+        let mut shallowest_leaf = None;
+        let mut deepest_node = None;
+        root.depth_extremes(0, &mut shallowest_leaf, &mut deepest_node);
+        let (leaf, leaf_depth) = shallowest_leaf?;
+        let (deepest, deepest_depth) = deepest_node?;
+        Some(((leaf.clone(), leaf_depth), (deepest.clone(), deepest_depth)))
+    }
+
+    /// Find the leaf closest to the root along with its depth
+    pub fn shallowest_leaf(&self) -> Option<(T, usize)>
+    where
+        T: Clone,
+    {
+        self.depth_extremes().map(|(leaf, _)| leaf)
+    }
+
+    /// Find the deepest node along with its depth
+    pub fn deepest_node(&self) -> Option<(T, usize)>
+    where
+        T: Clone,
+    {
+        self.depth_extremes().map(|(_, deepest)| deepest)
+    }
+
+    /// Render the BST as a Graphviz `digraph`, e.g. to pipe into `dot -Tpng`.
+    /// Nodes are labelled with their values, and invisible placeholders stand in for a
+    /// missing child so left and right children stay on their own sides.
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Display,
+    {
+        let mut out = String::from("digraph BST {\n");
+        if let Some(ref root) = self.root {
+            root.write_dot(&mut out, &mut 0);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Check whether both BSTs hold equal values arranged in the same shape, i.e. every node has
+    /// an equal value and the same children present. Unlike `==`, which only compares contents,
+    /// this tells apart a balanced tree from a vine holding the same values.
+    pub fn structural_eq(&self, other: &Self) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut stack = vec![(self.root.as_deref(), other.root.as_deref())];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (None, None) => {}
+                (Some(a), Some(b)) if a.size == b.size && a.value == b.value => {
+                    stack.push((a.left.as_deref(), b.left.as_deref()));
+                    stack.push((a.right.as_deref(), b.right.as_deref()));
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Check whether the shape of the BST is its own mirror image around the root, comparing only
+    /// which children are present, since no BST with two or more values is symmetric by value
+    pub fn is_symmetric(&self) -> bool {
+        let Some(root) = self.root.as_deref() else {
+            return true;
+        };
+        let mut stack = vec![(root.left.as_deref(), root.right.as_deref())];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (None, None) => {}
+                (Some(a), Some(b)) if a.size == b.size => {
+                    stack.push((a.left.as_deref(), b.right.as_deref()));
+                    stack.push((a.right.as_deref(), b.left.as_deref()));
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Hash both the values and the shape of the BST.
+    /// Different hashes guarantee the trees differ; equal hashes don't guarantee
+    /// the trees are identical, since collisions are possible. Needs the `std` feature for its hasher.
+    #[cfg(feature = "std")]
+    pub fn structural_hash(&self) -> u64
+    where
+        T: Hash,
+    {
+        let mut hasher = DefaultHasher::new();
+        Node::hash_structure(&self.root, &mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<T, C: Comparator<T>> OrderedSet<T> for BinarySearchTree<T, C> {
+    fn first(&self) -> Option<&T> {
+        self.find_min()
+    }
+
+    fn last(&self) -> Option<&T> {
+        self.find_max()
+    }
+
+    fn nth(&self, n: usize) -> Option<&T> {
+        self.select(n)
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        self.contains(value)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+// Render the BST sideways with the root first and each child indented below its parent,
+// the left child before the right one
+impl<T: fmt::Display, C> fmt::Display for BinarySearchTree<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.root {
+            Some(ref root) => {
+                write!(f, "{}", root.value)?;
+                root.fmt_children(f)
+            }
+            None => write!(f, "<empty>"),
+        }
+    }
+}
+
+// List the values in sorted order as a set, walking iteratively; a derived impl would print the nested
+// nodes and recurse once per level
+impl<T: fmt::Debug, C> fmt::Debug for BinarySearchTree<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(InOrderIter::new(self.root.as_deref())).finish()
+    }
+}
+
+// Trees are equal when they hold the same values, however they were built and whatever their shape;
+// use `structural_eq` or `structural_hash` to tell apart trees that also differ in shape
+impl<T: PartialEq, C> PartialEq for BinarySearchTree<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        Node::size_of(&self.root) == Node::size_of(&other.root)
+            && InOrderIter::new(self.root.as_deref()).eq(InOrderIter::new(other.root.as_deref()))
+    }
+}
+
+impl<T: Eq, C> Eq for BinarySearchTree<T, C> {}
+
+// A derived clone would recurse once per level, like dropping
+impl<T: Clone> Clone for Node<T> {
+    fn clone(&self) -> Self {
+        *Node::clone_subtree(self)
+    }
+}
+
+impl<T: Clone, C: Clone> Clone for BinarySearchTree<T, C> {
+    fn clone(&self) -> Self {
+        BinarySearchTree {
+            root: self.root.as_deref().map(Node::clone_subtree),
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+// Dropping the nodes one `Box` at a time would recurse once per level
+impl<T, C> Drop for BinarySearchTree<T, C> {
+    fn drop(&mut self) {
+        Node::dismantle(self.root.take());
+    }
+}
+
+impl<T, C: Default> Default for BinarySearchTree<T, C> {
+    fn default() -> Self {
+        BinarySearchTree {
+            root: None,
+            cmp: C::default(),
+        }
+    }
+}
+
+// Sort and de-duplicate the values, then build a height-balanced BST from them in one pass
+impl<T: Ord> From<Vec<T>> for BinarySearchTree<T> {
+    fn from(mut values: Vec<T>) -> Self {
+        values.sort_unstable();
+        values.dedup();
+        BinarySearchTree {
+            root: Node::from_sorted_iter(values.len(), &mut values.into_iter()),
+            cmp: Natural,
+        }
+    }
+}
+
+// The set's values come out already sorted and distinct, so they are built balanced in one pass
+impl<T: Ord> From<BTreeSet<T>> for BinarySearchTree<T> {
+    fn from(set: BTreeSet<T>) -> Self {
+        BinarySearchTree {
+            root: Node::from_sorted_iter(set.len(), &mut set.into_iter()),
+            cmp: Natural,
+        }
+    }
+}
+
+// Move the values out in sorted order, which the standard set bulk-loads without re-sorting
+impl<T: Ord> From<BinarySearchTree<T>> for BTreeSet<T> {
+    fn from(tree: BinarySearchTree<T>) -> Self {
+        tree.into_sorted_vec().into_iter().collect()
+    }
+}
+
+impl<T, C: Comparator<T> + Default> FromIterator<T> for BinarySearchTree<T, C> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut bst = BinarySearchTree::default();
+        bst.extend(iter);
+        bst
+    }
+}
+
+// Duplicate values are skipped, just as `insert` would reject them
+impl<T, C: Comparator<T>> Extend<T> for BinarySearchTree<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert_if_absent(value);
+        }
+    }
+}
+
+impl<T, C> IntoIterator for BinarySearchTree<T, C> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    // Consume the BST, moving the values out in sorted order
+    fn into_iter(mut self) -> Self::IntoIter {
+        IntoIter::new(self.root.take())
+    }
+}
+
+/// Lazy in-order iterator over a BST, yielding references to the values in sorted order
+pub struct InOrderIter<'a, T> {
+    front: Vec<&'a Node<T>>,
+    back: Vec<&'a Node<T>>,
+    // Values not yet yielded from either end, read from the cached subtree size; the two stacks
+    // share nodes, so this is what stops the ends from crossing
+    remaining: usize,
+}
+
+impl<'a, T> InOrderIter<'a, T> {
+    fn new(root: Option<&'a Node<T>>) -> Self {
+        let mut iter = InOrderIter {
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining: root.map_or(0, |root| root.size),
+        };
+        iter.push_left_spine(root);
+        iter.push_right_spine(root);
+        iter
+    }
+
+    // Push a node and its chain of left descendants, so the smallest pending value is on top
+    fn push_left_spine(&mut self, mut node: Option<&'a Node<T>>) {
+        while let Some(current) = node {
+            self.front.push(current);
+            node = current.left.as_deref();
+        }
+    }
+
+    // Push a node and its chain of right descendants, so the largest pending value is on top
+    fn push_right_spine(&mut self, mut node: Option<&'a Node<T>>) {
+        while let Some(current) = node {
+            self.back.push(current);
+            node = current.right.as_deref();
+        }
+    }
+}
+
+impl<'a, T> Iterator for InOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.front.pop()?;
+        self.push_left_spine(node.right.as_deref());
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for InOrderIter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.back.pop()?;
+        self.push_right_spine(node.left.as_deref());
+        Some(&node.value)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for InOrderIter<'a, T> {}
+
+/// Lazy reverse in-order iterator over a BST, yielding references to the values from largest to smallest
+pub struct RevInOrderIter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> RevInOrderIter<'a, T> {
+    fn new(root: Option<&'a Node<T>>) -> Self {
+        let mut iter = RevInOrderIter { stack: Vec::new() };
+        iter.push_right_spine(root);
+        iter
+    }
+
+    // Push a node and its chain of right descendants, so the largest pending value is on top
+    fn push_right_spine(&mut self, mut node: Option<&'a Node<T>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = current.right.as_deref();
+        }
+    }
+}
+
+impl<'a, T> Iterator for RevInOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_right_spine(node.left.as_deref());
+        Some(&node.value)
+    }
+}
+
+/// Owning in-order iterator over a BST, moving the values out in sorted order
+pub struct IntoIter<T> {
+    stack: Vec<Box<Node<T>>>,
+}
+
+impl<T> IntoIter<T> {
+    fn new(root: Option<Box<Node<T>>>) -> Self {
+        let mut iter = IntoIter { stack: Vec::new() };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    // Push a detached node and its chain of left descendants, so the smallest pending value is on top
+    fn push_left_spine(&mut self, mut link: Option<Box<Node<T>>>) {
+        while let Some(mut node) = link {
+            link = node.left.take();
+            self.stack.push(node);
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut node = self.stack.pop()?;
+        self.push_left_spine(node.right.take());
+        Some(node.value)
+    }
+}
+
+// Values left unconsumed are freed without recursing, as when dropping the tree
+impl<T> Drop for IntoIter<T> {
+    fn drop(&mut self) {
+        for node in self.stack.drain(..) {
+            Node::dismantle(Some(node));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The tree the docs and the demo use
+    fn sample() -> BinarySearchTree<i32> {
+        [10, 5, 15, 3, 7, 12, 18].into_iter().collect()
+    }
+
+    // Build `0..n` as a vine of right children in O(n) by linking the nodes bottom-up
+    fn vine(n: i32) -> BinarySearchTree<i32> {
+        let mut root = None;
+        for value in (0..n).rev() {
+            let mut node = Box::new(Node::new(value));
+            node.right = root;
+            node.update();
+            root = Some(node);
+        }
+        BinarySearchTree { root, cmp: Natural }
+    }
+
+    // Insert `0..n` in ascending order, building a vine `n` levels deep, then delete from both ends
+    fn ascending_vine(n: i32) {
+        let mut tree = BinarySearchTree::new();
+        for value in 0..n {
+            tree.insert(value).unwrap();
+        }
+        assert_eq!(tree.height(), n as usize);
+        assert!(matches!(tree.insert(n - 1), Err(BSTError::DuplicateValue(_))));
+        tree.delete(n - 1).unwrap();
+        tree.delete(0).unwrap();
+        assert!(matches!(tree.delete(n), Err(BSTError::ValueNotFound(_))));
+        assert_eq!((tree.len(), tree.find_min(), tree.find_max()), (n as usize - 2, Some(&1), Some(&(n - 2))));
+    }
+
+    // A record ordered by its id alone, so a query can match a stored record with another payload
+    #[derive(Debug, Clone)]
+    struct Record {
+        id: u32,
+        payload: &'static str,
+    }
+
+    impl PartialEq for Record {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Eq for Record {}
+
+    impl PartialOrd for Record {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Record {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.id.cmp(&other.id)
+        }
+    }
+
+    // A value that can't be cloned, so the tree has to move it around
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Ticket(u32);
+
+    #[test]
+    fn insert_if_absent_descends_once() {
+        let calls = Cell::new(0);
+        let mut tree = BinarySearchTree::with_comparator(|a: &i32, b: &i32| {
+            calls.set(calls.get() + 1);
+            a.cmp(b)
+        });
+        for value in [10, 5, 15, 3, 7, 12, 18] {
+            tree.insert(value).unwrap();
+        }
+        calls.set(0);
+        assert!(tree.insert_if_absent(11));
+        assert_eq!(calls.get(), 3);
+        calls.set(0);
+        assert!(!tree.insert_if_absent(12));
+        assert_eq!(calls.get(), 3);
+        assert_eq!(tree.len(), 8);
+    }
+
+    #[test]
+    fn apply_diff_round_trips() {
+        let mut base = sample();
+        let target: BinarySearchTree<i32> = [3, 4, 7, 10, 18, 20].into_iter().collect();
+        let (added, removed) = base.content_diff(&target);
+        assert_eq!((added.as_slice(), removed.as_slice()), ([4, 20].as_slice(), [5, 12, 15].as_slice()));
+        base.apply_diff(&added, &removed).unwrap();
+        assert_eq!(base, target);
+        assert!(base.verify_caches().is_ok());
+    }
+
+    #[test]
+    fn apply_diff_rolls_back_a_mismatched_diff() {
+        let mut base = sample();
+        assert!(matches!(base.apply_diff(&[4, 10], &[]), Err(BSTError::DuplicateValue(10))));
+        assert!(matches!(base.apply_diff(&[4], &[3, 9]), Err(BSTError::ValueNotFound(9))));
+        assert_eq!(base, sample());
+    }
+
+    #[test]
+    fn concat_links_a_disjoint_tree() {
+        let mut tree = sample();
+        let high: BinarySearchTree<i32> = [25, 20, 30].into_iter().collect();
+        tree.concat(high).unwrap();
+        assert_eq!(tree.in_order_traversal(), [3, 5, 7, 10, 12, 15, 18, 20, 25, 30]);
+        assert_eq!(tree.height(), 4);
+        assert!(tree.is_balanced() && tree.is_valid_bst() && tree.verify_caches().is_ok());
+        tree.concat(BinarySearchTree::new()).unwrap();
+        assert_eq!(tree.len(), 10);
+        let mut empty = BinarySearchTree::new();
+        empty.concat(sample()).unwrap();
+        assert!(empty.structural_eq(&sample()));
+    }
+
+    #[test]
+    fn concat_rebalances_trees_of_any_heights() {
+        let mut appended = BinarySearchTree::new();
+        let mut prepended = BinarySearchTree::new();
+        for value in 0..1000 {
+            appended.concat([value].into_iter().collect()).unwrap();
+            let mut head: BinarySearchTree<i32> = [-value].into_iter().collect();
+            head.concat(prepended).unwrap();
+            prepended = head;
+        }
+        for tree in [&appended, &prepended] {
+            assert_eq!(tree.len(), 1000);
+            assert!(tree.is_balanced() && tree.is_valid_bst() && tree.verify_caches().is_ok());
+            assert!(tree.height() as f64 <= 1.44 * 1000f64.log2());
+        }
+        let values: Vec<i32> = (0..500).collect();
+        for cut in [3, 497] {
+            let mut low = BinarySearchTree::from_sorted_slice(&values[..cut]);
+            low.concat(BinarySearchTree::from_sorted_slice(&values[cut..])).unwrap();
+            assert_eq!(low.in_order_traversal(), values);
+            assert!(low.is_balanced() && low.verify_caches().is_ok());
+        }
+    }
+
+    #[test]
+    fn concat_rejects_overlapping_ranges() {
+        let mut tree = sample();
+        let overlapping: BinarySearchTree<i32> = [11, 40].into_iter().collect();
+        assert!(matches!(tree.concat(overlapping), Err(BSTError::OverlappingRanges)));
+        let touching: BinarySearchTree<i32> = [18, 40].into_iter().collect();
+        assert!(matches!(tree.concat(touching), Err(BSTError::OverlappingRanges)));
+        assert!(tree.structural_eq(&sample()));
+    }
+
+    #[test]
+    fn verify_caches_lists_stale_nodes() {
+        let mut tree = sample();
+        assert_eq!(tree.verify_caches(), Ok(()));
+        let root = tree.root.as_mut().unwrap();
+        root.left.as_mut().unwrap().left.as_mut().unwrap().height = 2;
+        root.right.as_mut().unwrap().size = 4;
+        assert_eq!(tree.verify_caches(), Err(vec![3, 15]));
+    }
+
+    #[test]
+    fn leaves_are_one_more_than_full_nodes() {
+        let trees: [BinarySearchTree<i32>; 4] = [
+            sample(),
+            (1..=20).collect(),
+            [10, 5, 15, 3, 12, 18, 20, 2, 1].into_iter().collect(),
+            [8].into_iter().collect(),
+        ];
+        for tree in &trees {
+            assert_eq!(tree.count_leaves(), tree.count_full_nodes() + 1);
+            assert_eq!(tree.count_full_nodes() + tree.count_half_nodes() + tree.count_leaves(), tree.len());
+        }
+        assert_eq!((sample().count_full_nodes(), sample().count_half_nodes()), (3, 0));
+    }
+
+    #[test]
+    fn replace_subtree_swaps_a_fitting_branch() {
+        let mut tree = sample();
+        let branch: BinarySearchTree<i32> = [13, 11, 14].into_iter().collect();
+        let old = tree.replace_subtree(&12, branch).unwrap();
+        assert_eq!(old.in_order_traversal(), [12]);
+        assert_eq!(tree.in_order_traversal(), [3, 5, 7, 10, 11, 13, 14, 15, 18]);
+        assert!(tree.is_valid_bst() && tree.verify_caches().is_ok());
+        let old = tree.replace_subtree(&5, BinarySearchTree::new()).unwrap();
+        assert_eq!(old.in_order_traversal(), [3, 5, 7]);
+        assert_eq!(tree.in_order_traversal(), [10, 11, 13, 14, 15, 18]);
+    }
+
+    #[test]
+    fn replace_subtree_rejects_an_out_of_range_branch() {
+        let mut tree = sample();
+        let too_high: BinarySearchTree<i32> = [4, 11].into_iter().collect();
+        assert!(matches!(tree.replace_subtree(&5, too_high), Err(BSTError::OutOfRange)));
+        let too_low: BinarySearchTree<i32> = [10].into_iter().collect();
+        assert!(matches!(tree.replace_subtree(&12, too_low), Err(BSTError::OutOfRange)));
+        assert!(matches!(tree.replace_subtree(&4, BinarySearchTree::new()), Err(BSTError::ValueNotFound(4))));
+        assert!(tree.structural_eq(&sample()));
+    }
+
+    #[test]
+    fn iter_matches_in_order_traversal() {
+        let tree = sample();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), tree.in_order_traversal());
+        assert_eq!(tree.iter().len(), 7);
+        assert_eq!(BinarySearchTree::<i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn collecting_skips_duplicates() {
+        let mut tree: BinarySearchTree<i32> = vec![8, 3, 8, 1, 3, 9].into_iter().collect();
+        assert_eq!(tree.in_order_traversal(), [1, 3, 8, 9]);
+        tree.extend([9, 2, 1]);
+        assert_eq!(tree.in_order_traversal(), [1, 2, 3, 8, 9]);
+    }
+
+    #[test]
+    fn contains_borrows_the_query() {
+        let words: BinarySearchTree<String> = ["pear", "apple", "fig"].iter().map(|word| word.to_string()).collect();
+        let query = String::from("fig");
+        assert!(words.contains(&query));
+        assert!(!words.contains(&String::from("kiwi")));
+        assert_eq!(query, "fig");
+    }
+
+    #[test]
+    fn ascending_inserts_build_a_deep_vine() {
+        ascending_vine(5_000);
+    }
+
+    // Far deeper than a test thread's stack could take one frame per level
+    #[test]
+    fn deep_vines_insert_and_delete_at_the_bottom() {
+        let n = 200_000;
+        let mut tree = vine(n);
+        tree.insert(n).unwrap();
+        assert!(matches!(tree.insert(n - 1), Err(BSTError::DuplicateValue(_))));
+        assert!(tree.contains(&n) && !tree.contains(&(n + 1)));
+        assert_eq!(tree.height(), n as usize + 1);
+        tree.delete(n - 1).unwrap();
+        assert!(matches!(tree.delete(n - 1), Err(BSTError::ValueNotFound(_))));
+        assert_eq!(tree.remove(&n), Some(n));
+        assert_eq!((tree.pop_max(), tree.pop_min()), (Some(n - 2), Some(0)));
+        assert_eq!((tree.len(), tree.height()), (n as usize - 3, n as usize - 3));
+        assert_eq!((tree.find_min(), tree.find_max()), (Some(&1), Some(&(n - 3))));
+    }
+
+    #[test]
+    fn floor_and_ceiling_track_the_best_candidate() {
+        let tree = sample();
+        assert_eq!((tree.floor(&11), tree.ceiling(&11)), (Some(&10), Some(&12)));
+        assert_eq!((tree.floor(&2), tree.ceiling(&20)), (None, None));
+        assert_eq!((tree.floor(&20), tree.ceiling(&2)), (Some(&18), Some(&3)));
+        for value in tree.iter() {
+            assert_eq!((tree.floor(value), tree.ceiling(value)), (Some(value), Some(value)));
+        }
+    }
+
+    #[test]
+    fn balance_flattens_a_sorted_build() {
+        let mut tree: BinarySearchTree<i32> = (1..=15).collect();
+        assert_eq!(tree.height(), 15);
+        tree.balance();
+        assert_eq!(tree.height(), 4);
+        assert!(tree.is_balanced() && tree.verify_caches().is_ok());
+        assert_eq!(tree.in_order_traversal(), (1..=15).collect::<Vec<_>>());
+        for n in [0, 1, 2, 100, 1000] {
+            let mut tree: BinarySearchTree<i32> = (0..n).collect();
+            tree.balance();
+            assert_eq!(tree.height(), if n == 0 { 0 } else { n.ilog2() as usize + 1 });
+            assert!(tree.is_balanced() && tree.is_valid_bst());
+        }
+    }
+
+    #[test]
+    fn from_sorted_slice_builds_a_balanced_tree() {
+        let values: Vec<i32> = (0..100).map(|i| i * 3).collect();
+        let tree = BinarySearchTree::from_sorted_slice(&values);
+        assert!(tree.is_balanced() && tree.is_valid_bst() && tree.verify_caches().is_ok());
+        assert_eq!(tree.in_order_traversal(), values);
+        assert_eq!(tree.height(), 7);
+    }
+
+    #[test]
+    fn from_sorted_slice_trusts_its_input() {
+        let unsorted = BinarySearchTree::from_sorted_slice(&[3, 1, 2]);
+        assert!(!unsorted.is_valid_bst());
+        assert!(!unsorted.contains(&3));
+        let duplicated = BinarySearchTree::from_sorted_slice(&[1, 2, 2, 3]);
+        assert!(!duplicated.is_valid_bst());
+        assert_eq!(duplicated.len(), 4);
+    }
+
+    #[test]
+    fn get_returns_the_stored_instance() {
+        let records: BinarySearchTree<Record> = [(2, "two"), (1, "one")].map(|(id, payload)| Record { id, payload }).into_iter().collect();
+        let stored = records.get(&Record { id: 1, payload: "query" }).unwrap();
+        assert_eq!(stored.payload, "one");
+        assert!(records.get(&Record { id: 3, payload: "query" }).is_none());
+    }
+
+    #[test]
+    fn map_iterates_sorted_by_key() {
+        let mut ages = BSTMap::new();
+        assert_eq!(ages.insert("carol", 41), None);
+        assert_eq!(ages.insert("alice", 30), None);
+        assert_eq!(ages.insert("bob", 41), None);
+        assert_eq!(ages.insert("alice", 31), Some(30));
+        *ages.get_mut(&"carol").unwrap() += 1;
+        assert_eq!(ages.iter().collect::<Vec<_>>(), [(&"alice", &31), (&"bob", &41), (&"carol", &42)]);
+        assert_eq!((ages.remove(&"bob"), ages.remove(&"bob"), ages.len()), (Some(41), None, 2));
+    }
+
+    #[test]
+    fn multiset_counts_repeats() {
+        let mut multiset = BSTMultiset::new();
+        for value in [5, 3, 5, 5] {
+            multiset.insert(value);
+        }
+        assert_eq!((multiset.count_of(&5), multiset.count_of(&4)), (3, 0));
+        assert_eq!((multiset.len(), multiset.distinct_len()), (4, 2));
+        assert_eq!(multiset.in_order_traversal(), [3, 5, 5, 5]);
+        assert!(multiset.remove_one(&3) && !multiset.remove_one(&3));
+        assert_eq!(multiset.in_order_traversal(), [5, 5, 5]);
+    }
+
+    #[test]
+    fn into_iter_moves_values_out_in_order() {
+        let words: BinarySearchTree<String> = ["pear", "apple", "fig"].iter().map(|word| word.to_string()).collect();
+        assert_eq!(words.into_iter().collect::<Vec<String>>(), ["apple", "fig", "pear"]);
+        let mut partly = sample().into_iter();
+        assert_eq!((partly.next(), partly.next()), (Some(3), Some(5)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips_through_json() {
+        let vine: BinarySearchTree<i32> = (1..=7).collect();
+        let json = serde_json::to_string(&vine).unwrap();
+        assert_eq!(json, "[1,2,3,4,5,6,7]");
+        let restored: BinarySearchTree<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, vine);
+        assert!(restored.is_balanced() && restored.verify_caches().is_ok());
+        let messy: BinarySearchTree<i32> = serde_json::from_str("[12, 3, 18, 3, 10]").unwrap();
+        assert_eq!(messy.in_order_traversal(), [3, 10, 12, 18]);
+        assert!(messy.is_valid_bst());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_builds_values_that_cannot_be_cloned() {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Id(u32);
+        impl<'de> serde::Deserialize<'de> for Id {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                u32::deserialize(deserializer).map(Id)
+            }
+        }
+        let ids: BinarySearchTree<Id> = serde_json::from_str("[7, 2, 9, 2]").unwrap();
+        assert!(ids.iter().eq(&[Id(2), Id(7), Id(9)]));
+        assert!(ids.is_balanced() && ids.len() == ids.count_nodes());
+    }
+
+    #[test]
+    fn to_dot_links_parents_to_children() {
+        let dot = sample().to_dot();
+        assert!(dot.starts_with("digraph BST {\n") && dot.ends_with("}\n"));
+        for edge in ["\"10\" -> \"5\"", "\"10\" -> \"15\"", "\"5\" -> \"3\"", "\"15\" -> \"18\""] {
+            assert!(dot.contains(edge), "missing {}", edge);
+        }
+        assert!(!dot.contains("\"5\" -> \"12\""));
+        assert_eq!(BinarySearchTree::<i32>::new().to_dot(), "digraph BST {\n}\n");
+    }
+
+    #[test]
+    fn display_draws_one_value_per_line() {
+        let rendered = sample().to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines, ["10", "├── 5", "│   ├── 3", "│   └── 7", "└── 15", "    ├── 12", "    └── 18"]);
+        assert_eq!(BinarySearchTree::<i32>::new().to_string(), "<empty>");
+        assert_eq!(format!("{:?}", sample()), "{3, 5, 7, 10, 12, 15, 18}");
+    }
+
+    #[test]
+    fn clear_empties_the_tree() {
+        let mut tree = sample();
+        tree.clear();
+        assert!(tree.is_empty());
+        assert!(tree.in_order_traversal().is_empty());
+        tree.insert(4).unwrap();
+        assert_eq!(tree.len(), 1);
+        assert!(BinarySearchTree::<i32>::default().is_empty());
+    }
+
+    #[test]
+    fn delete_moves_non_clone_values() {
+        let mut tickets: BinarySearchTree<Ticket> = [10, 5, 15, 3, 7, 12, 18].into_iter().map(Ticket).collect();
+        tickets.delete(Ticket(10)).unwrap();
+        tickets.delete(Ticket(3)).unwrap();
+        assert!(matches!(tickets.delete(Ticket(3)), Err(BSTError::ValueNotFound(Ticket(3)))));
+        assert_eq!(tickets.iter().map(|ticket| ticket.0).collect::<Vec<_>>(), [5, 7, 12, 15, 18]);
+        assert_eq!(tickets.pop_min(), Some(Ticket(5)));
+    }
+
+    #[test]
+    fn remove_returns_the_value_once() {
+        let mut tree = sample();
+        assert_eq!(tree.remove(&15), Some(15));
+        assert_eq!(tree.remove(&15), None);
+        assert!(matches!(tree.delete(15), Err(BSTError::ValueNotFound(15))));
+        assert_eq!(tree.in_order_traversal(), [3, 5, 7, 10, 12, 18]);
+    }
+
+    #[test]
+    fn pop_min_and_pop_max_drain_in_order() {
+        let mut tree = sample();
+        let mut drained = Vec::new();
+        while let Some(value) = tree.pop_min() {
+            drained.push(value);
+        }
+        assert_eq!(drained, [3, 5, 7, 10, 12, 15, 18]);
+        assert_eq!((tree.pop_min(), tree.pop_max()), (None, None));
+        let mut tree = sample();
+        assert_eq!((tree.pop_max(), tree.pop_max(), tree.len()), (Some(18), Some(15), 5));
+        assert!(tree.verify_caches().is_ok());
+    }
+
+    #[test]
+    fn range_includes_both_bounds() {
+        let tree = sample();
+        assert_eq!(tree.range(&5, &12), [5, 7, 10, 12]);
+        assert_eq!(tree.range(&4, &6), [5]);
+        assert_eq!(tree.range(&7, &7), [7]);
+        assert!(tree.range(&8, &9).is_empty());
+        assert!(tree.range(&12, &5).is_empty());
+        assert_eq!(tree.range(&0, &100), tree.in_order_traversal());
+    }
+
+    #[test]
+    fn count_in_range_matches_range() {
+        let tree = sample();
+        assert_eq!((tree.count_in_range(&4, &16), tree.count_in_range(&16, &4)), (5, 0));
+        for (low, high) in [(0, 100), (5, 12), (8, 9), (18, 18), (3, 2)] {
+            assert_eq!(tree.count_in_range(&low, &high), tree.range(&low, &high).len());
+        }
+    }
+
+    #[test]
+    fn sizes_stay_fresh_after_deletions() {
+        let mut tree: BinarySearchTree<i32> = (0..200).map(|i| i * 37 % 200).collect();
+        for value in (0..200).filter(|value| value % 3 == 0) {
+            tree.delete(value).unwrap();
+        }
+        assert_eq!(tree.len(), tree.count_nodes());
+        assert!(tree.verify_caches().is_ok());
+        let values = tree.in_order_traversal();
+        for (k, value) in values.iter().enumerate() {
+            assert_eq!((tree.select(k), tree.rank(value)), (Some(value), k));
+        }
+        assert_eq!(tree.select(values.len()), None);
+        assert_eq!((tree.rank(&-1), tree.rank(&1000)), (0, values.len()));
+    }
+
+    #[test]
+    fn kth_is_one_based_and_bounded() {
+        let tree = sample();
+        assert_eq!((tree.kth_smallest(1), tree.kth_largest(1)), (Some(&3), Some(&18)));
+        assert_eq!((tree.kth_smallest(7), tree.kth_largest(3)), (Some(&18), Some(&12)));
+        assert_eq!((tree.kth_smallest(0), tree.kth_largest(0)), (None, None));
+        assert_eq!((tree.kth_smallest(8), tree.kth_largest(8)), (None, None));
+    }
+
+    #[test]
+    fn equality_ignores_shape() {
+        let forward: BinarySearchTree<i32> = [10, 5, 15].into_iter().collect();
+        let backward: BinarySearchTree<i32> = [15, 10, 5].into_iter().collect();
+        assert_eq!(forward, backward);
+        assert!(!forward.structural_eq(&backward));
+        assert_ne!(forward, sample());
+        let subset: BinarySearchTree<i32> = [10, 5].into_iter().collect();
+        assert_ne!(forward, subset);
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let original = sample();
+        let mut copy = original.clone();
+        assert!(copy.structural_eq(&original) && copy.verify_caches().is_ok());
+        copy.delete(10).unwrap();
+        copy.insert(20).unwrap();
+        assert_eq!(copy.in_order_traversal(), [3, 5, 7, 12, 15, 18, 20]);
+        assert_eq!(original.in_order_traversal(), [3, 5, 7, 10, 12, 15, 18]);
+    }
+
+    #[test]
+    fn deep_vines_drop_without_overflowing() {
+        let deep = vine(300_000);
+        assert_eq!((deep.len(), deep.height()), (300_000, 300_000));
+        let ascending: Vec<i32> = (0..300_000).collect();
+        assert_eq!(deep.pre_order_traversal(), ascending);
+        assert!(deep.in_order_with_depth().iter().all(|&(value, depth)| value as usize == depth));
+        assert!(deep.post_order_traversal().into_iter().eq(ascending.into_iter().rev()));
+        let copy = deep.clone();
+        drop(deep);
+        let mut partly = copy.into_iter();
+        assert_eq!(partly.next(), Some(0));
+        drop(partly);
+    }
+
+    #[test]
+    fn cached_height_matches_recomputation() {
+        let mut tree = BinarySearchTree::new();
+        let deepest = |tree: &BinarySearchTree<i32>| tree.in_order_with_depth().iter().map(|&(_, depth)| depth + 1).max().unwrap_or(0);
+        for step in 0..500 {
+            let value = step * 7919 % 257;
+            if step % 3 == 2 {
+                tree.remove(&value);
+            } else {
+                tree.insert_if_absent(value);
+            }
+            assert_eq!(tree.height(), deepest(&tree));
+        }
+        assert!(tree.verify_caches().is_ok());
+    }
+
+    #[test]
+    fn is_balanced_checks_every_node() {
+        // 12 has a chain of two left children and no right one, while each level above it is within one
+        let tree: BinarySearchTree<i32> = [50, 25, 75, 12, 37, 60, 90, 6, 3, 30, 55, 95].into_iter().collect();
+        assert_eq!(tree.balance_factor(&50), Some(1));
+        assert_eq!(tree.balance_factor(&25), Some(1));
+        assert_eq!(tree.balance_factor(&12), Some(2));
+        assert!(!tree.is_balanced());
+        assert!(sample().is_balanced());
+    }
+
+    #[test]
+    fn avl_rebalances_ascending_inserts() {
+        let mut avl = AvlTree::new();
+        for value in 1..=1000 {
+            avl.insert(value).unwrap();
+        }
+        assert!(avl.height() as f64 <= 1.44 * 1000f64.log2());
+        assert!(avl.is_balanced() && avl.verify_caches().is_ok());
+        assert!(matches!(avl.insert(500), Err(BSTError::DuplicateValue(500))));
+        for value in 1..=500 {
+            avl.delete(value).unwrap();
+        }
+        assert!(avl.is_balanced() && avl.verify_caches().is_ok());
+        assert_eq!((avl.len(), avl.find_min(), avl.search(500)), (500, Some(&501), false));
+        assert_eq!(avl.in_order_traversal(), (501..=1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn custom_comparator_orders_by_length() {
+        let mut by_length = BinarySearchTree::with_comparator(|a: &&str, b: &&str| a.len().cmp(&b.len()));
+        for word in ["banana", "fig", "apple", "kiwi"] {
+            by_length.insert(word).unwrap();
+        }
+        assert_eq!(by_length.in_order_traversal(), ["fig", "kiwi", "apple", "banana"]);
+        assert_eq!(by_length.get(&"pear"), Some(&"kiwi"));
+        assert!(matches!(by_length.insert("plum"), Err(BSTError::DuplicateValue("plum"))));
+        assert!(by_length.is_valid_bst());
+    }
+
+    #[test]
+    fn is_valid_bst_checks_against_every_ancestor() {
+        let mut tree = sample();
+        assert!(tree.is_valid_bst());
+        // 11 is still above its parent 5, but sits left of the root 10
+        tree.root.as_mut().unwrap().left.as_mut().unwrap().right.as_mut().unwrap().value = 11;
+        assert!(!tree.is_valid_bst());
+        let mut tree = sample();
+        tree.root.as_mut().unwrap().right.as_mut().unwrap().left.as_mut().unwrap().value = 15;
+        assert!(!tree.is_valid_bst());
+    }
+
+    #[test]
+    fn lowest_common_ancestor_of_an_ancestor_is_itself() {
+        let tree = sample();
+        assert_eq!(tree.lowest_common_ancestor(&3, &7), Some(&5));
+        assert_eq!(tree.lowest_common_ancestor(&3, &12), Some(&10));
+        assert_eq!(tree.lowest_common_ancestor(&5, &3), Some(&5));
+        assert_eq!(tree.lowest_common_ancestor(&18, &10), Some(&10));
+        assert_eq!(tree.lowest_common_ancestor(&7, &7), Some(&7));
+        assert_eq!((tree.lowest_common_ancestor(&3, &4), tree.lowest_common_ancestor(&4, &3)), (None, None));
+    }
+
+    #[test]
+    fn invert_swaps_children() {
+        let original = sample();
+        let mut mirrored = original.clone();
+        mirrored.invert();
+        let root = mirrored.root.as_deref().unwrap();
+        assert_eq!((root.left.as_ref().unwrap().value, root.right.as_ref().unwrap().value), (15, 5));
+        assert!(mirrored.in_order_traversal().into_iter().eq(original.iter_rev().copied()));
+        assert_eq!(mirrored.pre_order_traversal(), [10, 15, 18, 12, 5, 7, 3]);
+        assert!(original.inverted().structural_eq(&mirrored));
+    }
+
+    #[test]
+    fn diameter_of_a_vine_spans_it() {
+        assert_eq!(BinarySearchTree::<i32>::new().diameter(), 0);
+        assert_eq!(BinarySearchTree::from_sorted_slice(&[1]).diameter(), 0);
+        assert_eq!(sample().diameter(), 4);
+        let degenerate: BinarySearchTree<i32> = (0..50).collect();
+        assert_eq!(degenerate.diameter(), 49);
+    }
+
+    #[test]
+    fn fold_in_order_sums_the_sample() {
+        let tree = sample();
+        assert_eq!(tree.fold_in_order(0, |sum, value| sum + value), 70);
+        let mut visited = Vec::new();
+        tree.for_each_in_order(|value| visited.push(*value));
+        assert_eq!(visited, tree.in_order_traversal());
+    }
+
+    #[test]
+    fn retain_keeps_matching_values() {
+        let mut tree = sample();
+        tree.retain(|value| value % 2 == 0);
+        assert_eq!(tree.in_order_traversal(), [10, 12, 18]);
+        assert!(tree.is_valid_bst() && tree.verify_caches().is_ok());
+        tree.retain(|_| false);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn split_and_merge_round_trip() {
+        let (below, above) = sample().split(&10);
+        assert_eq!((below.in_order_traversal(), above.in_order_traversal()), (vec![3, 5, 7], vec![10, 12, 15, 18]));
+        assert!(below.is_balanced() && above.is_balanced());
+        let merged = above.merge(below).unwrap();
+        assert_eq!(merged, sample());
+        assert!(merged.is_valid_bst() && merged.verify_caches().is_ok());
+        let (below, above) = sample().split(&100);
+        assert_eq!((below.len(), above.len()), (7, 0));
+        let overlapping: BinarySearchTree<i32> = [4, 6].into_iter().collect();
+        assert!(matches!(sample().merge(overlapping), Err(BSTError::OverlappingRanges)));
+    }
+
+    #[test]
+    fn set_operations_collapse_duplicates() {
+        let left: BinarySearchTree<i32> = [10, 5, 15, 3, 7].into_iter().collect();
+        let right: BinarySearchTree<i32> = [7, 10, 12, 18, 20].into_iter().collect();
+        assert_eq!(left.union(&right).in_order_traversal(), [3, 5, 7, 10, 12, 15, 18, 20]);
+        assert_eq!(left.intersection(&right).in_order_traversal(), [7, 10]);
+        assert_eq!(left.difference(&right).in_order_traversal(), [3, 5, 15]);
+        assert_eq!(right.difference(&left).in_order_traversal(), [12, 18, 20]);
+        assert!(left.union(&right).is_balanced());
+        assert!(left.intersection(&BinarySearchTree::new()).is_empty());
+    }
+
+    #[test]
+    fn insert_or_replace_updates_the_payload() {
+        let mut records = BinarySearchTree::new();
+        assert!(records.insert_or_replace(Record { id: 1, payload: "stored" }).is_none());
+        let replaced = records.insert_or_replace(Record { id: 1, payload: "updated" }).unwrap();
+        assert_eq!(replaced.payload, "stored");
+        assert_eq!(records.get(&Record { id: 1, payload: "query" }).unwrap().payload, "updated");
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn get_or_insert_with_calls_the_closure_once() {
+        let mut records = BinarySearchTree::new();
+        let query = Record { id: 3, payload: "query" };
+        assert_eq!(records.get_or_insert_with(&query, || Record { id: 3, payload: "created" }).payload, "created");
+        let again = records.get_or_insert_with(&query, || panic!("the record is already there"));
+        assert_eq!(again.payload, "created");
+        assert_eq!(records.get_or_insert(Record { id: 3, payload: "ignored" }).payload, "created");
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn entry_reads_updates_inserts_and_removes() {
+        let mut records: BinarySearchTree<Record> = [Record { id: 1, payload: "one" }].into_iter().collect();
+        let modified = records.entry(Record { id: 1, payload: "unused" }).and_modify(|record| record.payload = "modified").or_insert();
+        assert_eq!(modified.payload, "modified");
+        let inserted = records.entry(Record { id: 2, payload: "fresh" }).and_modify(|record| record.payload = "modified").or_insert();
+        assert_eq!(inserted.payload, "fresh");
+        match records.entry(Record { id: 1, payload: "unused" }) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove().payload, "modified"),
+            Entry::Vacant(_) => panic!("1 is in the tree"),
+        }
+        match records.entry(Record { id: 9, payload: "declined" }) {
+            Entry::Vacant(entry) => assert_eq!(entry.into_value().payload, "declined"),
+            Entry::Occupied(_) => panic!("9 isn't in the tree"),
+        }
+        assert_eq!(records.in_order_traversal().iter().map(|record| record.id).collect::<Vec<_>>(), [2]);
+    }
+
+    // Insert every value, bailing out with the first error
+    fn collect_unique(values: &[i32]) -> Result<BinarySearchTree<i32>, Box<dyn core::error::Error>> {
+        let mut tree = BinarySearchTree::new();
+        for &value in values {
+            tree.insert(value)?;
+        }
+        Ok(tree)
+    }
+
+    #[test]
+    fn errors_box_with_their_value() {
+        assert_eq!(collect_unique(&[4, 8, 15]).unwrap().len(), 3);
+        let error = collect_unique(&[4, 8, 15, 8]).unwrap_err();
+        assert_eq!(error.to_string(), "Duplicate value: cannot insert 8 twice");
+        let error = sample().delete(4).unwrap_err();
+        assert_eq!(error.to_string(), "Value not found: 4 isn't in the tree");
+    }
+
+    #[test]
+    fn in_place_walk_restores_the_shape() {
+        for mut tree in [sample(), (0..2000).collect(), vine(2000), BinarySearchTree::new()] {
+            let before = tree.clone();
+            let mut visited = Vec::new();
+            tree.morris_for_each(|value| visited.push(*value));
+            assert_eq!(visited, before.in_order_traversal());
+            assert!(tree.structural_eq(&before) && tree.is_valid_bst() && tree.verify_caches().is_ok());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn in_place_walk_restores_the_shape_when_the_callback_panics() {
+        let mut tree: BinarySearchTree<i32> = (0..100).map(|i| i * 37 % 100).collect();
+        let shape = tree.structural_hash();
+        for stop in [0, 1, 37, 99] {
+            let walk = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                tree.for_each_in_order_in_place(|value| assert_ne!(*value, stop));
+            }));
+            assert!(walk.is_err());
+            assert!(tree.structural_hash() == shape && tree.verify_caches().is_ok());
+        }
+    }
+
+    // Reference traversals that recurse the obvious way, for trees shallow enough for the stack
+    fn recursive_orders(link: &Option<Box<Node<i32>>>, pre: &mut Vec<i32>, post: &mut Vec<i32>) {
+        if let Some(node) = link {
+            pre.push(node.value);
+            recursive_orders(&node.left, pre, post);
+            recursive_orders(&node.right, pre, post);
+            post.push(node.value);
+        }
+    }
+
+    #[test]
+    fn iterative_orders_match_recursive_ones() {
+        let balanced = BinarySearchTree::from_sorted_slice(&(0..3000).collect::<Vec<_>>());
+        let degenerate: BinarySearchTree<i32> = (0..3000).rev().collect();
+        let mixed: BinarySearchTree<i32> = (0..3000).map(|i| i * 1237 % 3000).collect();
+        for tree in [balanced, degenerate, mixed, sample()] {
+            let (mut pre, mut post) = (Vec::new(), Vec::new());
+            recursive_orders(&tree.root, &mut pre, &mut post);
+            assert_eq!(tree.pre_order_traversal(), pre);
+            assert_eq!(tree.post_order_traversal(), post);
+        }
+        assert_eq!(sample().post_order_traversal(), [3, 7, 5, 12, 18, 15, 10]);
+    }
+
+    #[test]
+    fn min_max_borrows_both_ends() {
+        assert_eq!(sample().min_max(), Some((&3, &18)));
+        assert_eq!(BinarySearchTree::<i32>::new().min_max(), None);
+        let single = BinarySearchTree::from_sorted_slice(&[42]);
+        let (min, max) = single.min_max().unwrap();
+        assert!(core::ptr::eq(min, max));
+        assert_eq!((single.peek_min(), single.peek_max()), (Some(&42), Some(&42)));
+    }
+
+    #[test]
+    fn insert_sorted_rebuilds_balanced() {
+        let mut tree = sample();
+        tree.insert_sorted(&[1, 2, 20, 25, 30]).unwrap();
+        assert_eq!(tree.in_order_traversal(), [1, 2, 3, 5, 7, 10, 12, 15, 18, 20, 25, 30]);
+        assert!(tree.is_balanced() && tree.verify_caches().is_ok());
+        assert!(matches!(tree.insert_sorted(&[4, 12]), Err(BSTError::DuplicateValue(12))));
+        assert!(matches!(tree.insert_sorted(&[6, 6]), Err(BSTError::DuplicateValue(6))));
+        assert!(matches!(tree.insert_sorted(&[9, 8]), Err(BSTError::OutOfRange)));
+        assert_eq!(tree.len(), 12);
+    }
+
+    #[test]
+    fn closest_prefers_the_floor_on_a_tie() {
+        let tree = sample();
+        assert_eq!((tree.closest(&11), tree.closest(&14), tree.closest(&100)), (Some(&10), Some(&15), Some(&18)));
+        for value in tree.iter() {
+            assert_eq!(tree.closest(value), Some(value));
+        }
+        assert_eq!(tree.closest(&-5), Some(&3));
+        assert_eq!(BinarySearchTree::<i32>::new().closest(&1), None);
+    }
+
+    #[test]
+    fn arena_matches_the_boxed_tree() {
+        let values: Vec<u32> = (0..10_000).map(|i| i * 7919 % 10_007).collect();
+        let mut arena: ArenaBST<u32> = values.iter().copied().collect();
+        let boxed: BinarySearchTree<u32> = values.iter().copied().collect();
+        assert_eq!(arena.in_order_traversal(), boxed.in_order_traversal());
+        assert_eq!(arena.pre_order_traversal(), boxed.pre_order_traversal());
+        assert_eq!(arena.post_order_traversal(), boxed.post_order_traversal());
+        assert_eq!((arena.len(), arena.height()), (boxed.len(), boxed.height()));
+        let slots = arena.slots();
+        for value in values.iter().step_by(2) {
+            assert_eq!(arena.remove(value), Some(*value));
+        }
+        arena.extend(values.iter().step_by(2).copied());
+        assert_eq!(arena.slots(), slots);
+        assert_eq!(arena.in_order_traversal(), boxed.in_order_traversal());
+    }
+
+    #[test]
+    fn rebuild_restores_minimal_height() {
+        let mut tree: BinarySearchTree<u32> = (0..10_000).map(|i| i * 7919 % 10_000).collect();
+        let mut arena: ArenaBST<u32> = (0..10_000).map(|i| i * 7919 % 10_000).collect();
+        for value in (0..10_000).filter(|value| value % 10 != 0) {
+            tree.delete(value).unwrap();
+            arena.delete(value).unwrap();
+        }
+        tree.rebuild();
+        arena.rebuild();
+        assert!(tree.is_balanced() && tree.verify_caches().is_ok());
+        assert_eq!((tree.len(), tree.height()), (1000, 10));
+        assert_eq!((arena.height(), arena.slots()), (10, 1000));
+        assert_eq!(arena.in_order_traversal(), tree.in_order_traversal());
+    }
+
+    #[test]
+    fn iter_rev_is_the_reversed_traversal() {
+        let tree = sample();
+        let mut reversed = tree.in_order_traversal();
+        reversed.reverse();
+        assert_eq!(tree.iter_rev().copied().collect::<Vec<_>>(), reversed);
+        assert_eq!(tree.iter_rev().take(3).collect::<Vec<_>>(), [&18, &15, &12]);
+        assert_eq!(BinarySearchTree::<i32>::new().iter_rev().next(), None);
+    }
+
+    #[test]
+    fn iter_meets_in_the_middle() {
+        let tree = sample();
+        let mut both_ends = tree.iter();
+        let mut interleaved = Vec::new();
+        while let Some(low) = both_ends.next() {
+            interleaved.push(*low);
+            interleaved.extend(both_ends.next_back());
+        }
+        assert_eq!(interleaved, [3, 18, 5, 15, 7, 12, 10]);
+        let mut shrinking = tree.iter();
+        assert_eq!((shrinking.next_back(), shrinking.len()), (Some(&18), 6));
+        assert!(tree.iter().rev().eq(tree.iter_rev()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn shared_tree_answers_from_several_threads() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<BinarySearchTree<u32>>();
+        assert_send_sync::<SharedBST<u32>>();
+        let shared = (0..1000).map(|i| i * 2).collect::<BinarySearchTree<u32>>().into_shared();
+        let handles: Vec<_> = (0..4)
+            .map(|thread| {
+                let shared = shared.clone();
+                std::thread::spawn(move || (thread..2000).step_by(4).filter(|value| shared.contains(value)).count())
+            })
+            .collect();
+        let found: usize = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+        assert_eq!((found, shared.len()), (1000, 1000));
+        assert_eq!(shared.range(&10, &16), [10, 12, 14, 16]);
+    }
+
+    #[test]
+    fn persistent_versions_share_untouched_nodes() {
+        let version1: PersistentBST<u32> = (0..1023).map(|i| i * 613 % 1023).collect();
+        let version2 = version1.insert(2000);
+        let version3 = version2.delete(&511);
+        assert!(version1.iter().copied().eq(0..1023));
+        assert_eq!((version1.len(), version2.len(), version3.len()), (1023, 1024, 1023));
+        assert!(version2.contains(&2000) && !version1.contains(&2000));
+        assert!(version2.contains(&511) && !version3.contains(&511));
+        // Only the nodes on the path down to the new leaf, well within twice log2(1024)
+        let copied = version2.len() - version2.shared_nodes(&version1);
+        assert!(copied <= 20, "the insert copied {} nodes", copied);
+    }
+
+    #[test]
+    fn subset_and_disjoint_handle_empty_trees() {
+        let tree = sample();
+        let pair: BinarySearchTree<i32> = [3, 7].into_iter().collect();
+        let empty = BinarySearchTree::new();
+        assert!(pair.is_subset(&tree) && !tree.is_subset(&pair));
+        assert!(empty.is_subset(&tree) && empty.is_subset(&empty) && !tree.is_subset(&empty));
+        assert!(empty.is_disjoint(&tree) && tree.is_disjoint(&empty));
+        assert!(!tree.is_disjoint(&pair) && tree.is_disjoint(&[2, 4, 20].into_iter().collect()));
+        assert!(!tree.intersects_range(&13, &14) && tree.intersects_range(&14, &15));
+        assert!(!empty.intersects_range(&0, &100));
+    }
+
+    #[test]
+    fn insert_all_counts_duplicates() {
+        let mut tree = BinarySearchTree::new();
+        let report = tree.insert_all([1, 2, 2, 3, 3, 3]);
+        assert_eq!((report.inserted, report.duplicates), (3, 3));
+        assert_eq!(tree.insert_all([3, 4]), InsertReport { inserted: 1, duplicates: 1 });
+        assert_eq!(tree.in_order_traversal(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn first_and_last_match_the_extremes() {
+        let tree = sample();
+        assert_eq!((tree.first(), tree.last()), (tree.find_min(), tree.find_max()));
+        assert_eq!((tree.first(), tree.last()), (tree.select(0), tree.select(tree.len() - 1)));
+        assert_eq!(tree.nth(3), Some(&10));
+        let empty = BinarySearchTree::<i32>::new();
+        assert_eq!((empty.first(), empty.last()), (None, None));
+    }
+
+    #[test]
+    fn subtree_height_of_present_and_missing_values() {
+        let tree = sample();
+        assert_eq!((tree.subtree_height(&10), tree.subtree_height(&5), tree.subtree_height(&3)), (Some(3), Some(2), Some(1)));
+        assert_eq!((tree.subtree_height(&4), tree.subtree_height(&100)), (None, None));
+        assert_eq!(BinarySearchTree::<i32>::new().subtree_height(&1), None);
+    }
+
+    #[test]
+    fn vec_round_trip_sorts_and_dedups() {
+        let tree = BinarySearchTree::from(vec![8, 3, 5, 1, 8, 9, 3, 2, 7, 5]);
+        assert!(tree.is_balanced() && tree.is_valid_bst());
+        assert_eq!(tree.into_sorted_vec(), [1, 2, 3, 5, 7, 8, 9]);
+        assert!(BinarySearchTree::<i32>::from(Vec::new()).into_sorted_vec().is_empty());
+    }
+
+    #[test]
+    fn replace_value_moves_the_key() {
+        let mut tree = sample();
+        tree.replace_value(&5, 6).unwrap();
+        assert_eq!(tree.in_order_traversal(), [3, 6, 7, 10, 12, 15, 18]);
+        assert!(!tree.contains(&5) && tree.is_valid_bst() && tree.verify_caches().is_ok());
+        tree.replace_value(&3, 20).unwrap();
+        assert_eq!(tree.in_order_traversal(), [6, 7, 10, 12, 15, 18, 20]);
+        assert!(matches!(tree.replace_value(&6, 12), Err(BSTError::DuplicateValue(12))));
+        assert!(matches!(tree.replace_value(&5, 4), Err(BSTError::ValueNotFound(5))));
+        assert_eq!(tree.len(), 7);
+    }
+
+    #[test]
+    fn stats_describe_the_sample() {
+        let expected = TreeStats { node_count: 7, height: 3, leaf_count: 4, min_depth: 2, max_depth: 2, is_balanced: true };
+        assert_eq!(sample().stats(), expected);
+        let skewed: BinarySearchTree<i32> = (0..5).collect();
+        let stats = skewed.stats();
+        assert_eq!((stats.height, stats.leaf_count, stats.min_depth, stats.max_depth, stats.is_balanced), (5, 1, 4, 4, false));
+    }
+
+    #[test]
+    fn lower_and_higher_differ_from_floor_and_ceiling_on_stored_keys() {
+        let tree = sample();
+        for query in 0..22 {
+            let stored = tree.contains(&query);
+            assert_eq!(tree.lower(&query) != tree.floor(&query), stored);
+            assert_eq!(tree.higher(&query) != tree.ceiling(&query), stored);
+        }
+        assert_eq!((tree.lower(&10), tree.higher(&10)), (Some(&7), Some(&12)));
+        assert_eq!((tree.lower(&3), tree.higher(&18)), (None, None));
+    }
+
+    #[test]
+    fn drain_range_removes_what_it_returns() {
+        let mut tree = sample();
+        assert_eq!(tree.drain_range(&5, &12), [5, 7, 10, 12]);
+        assert_eq!(tree.in_order_traversal(), [3, 15, 18]);
+        assert!(tree.verify_caches().is_ok() && tree.is_valid_bst());
+        assert!(tree.drain_range(&8, &9).is_empty() && tree.drain_range(&12, &5).is_empty());
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.drain_range(&0, &100), [3, 15, 18]);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn append_empties_the_other_tree() {
+        let mut tree = sample();
+        let mut other: BinarySearchTree<i32> = [4, 6, 20].into_iter().collect();
+        assert_eq!(tree.append(&mut other), InsertReport { inserted: 3, duplicates: 0 });
+        assert!(other.is_empty());
+        assert_eq!(tree.in_order_traversal(), [3, 4, 5, 6, 7, 10, 12, 15, 18, 20]);
+        assert!(tree.is_balanced() && tree.verify_caches().is_ok());
+        let mut repeats: BinarySearchTree<i32> = [4, 11].into_iter().collect();
+        assert_eq!(tree.append(&mut repeats), InsertReport { inserted: 1, duplicates: 1 });
+        assert_eq!(tree.len(), 11);
+    }
+
+    #[test]
+    fn partition_count_finds_the_split() {
+        let tree = sample();
+        assert_eq!(tree.partition_count(|value| *value >= 11), 4);
+        assert_eq!((tree.partition_count(|value| *value >= 0), tree.partition_count(|value| *value >= 100)), (0, 7));
+        assert_eq!(tree.partition_count(|value| *value >= 10), tree.rank(&10));
+    }
+
+    #[test]
+    fn into_top_k_takes_from_the_top() {
+        assert_eq!(sample().into_top_k(3), [18, 15, 12]);
+        assert!(sample().into_top_k(0).is_empty());
+        assert_eq!(sample().into_top_k(10), [18, 15, 12, 10, 7, 5, 3]);
+        let tickets: BinarySearchTree<Ticket> = [3, 1, 2].into_iter().map(Ticket).collect();
+        assert_eq!(tickets.into_top_k(2), [Ticket(3), Ticket(2)]);
+    }
+
+    #[test]
+    fn map_skips_mapped_duplicates() {
+        let remainders = sample().map(|value| value % 10);
+        assert_eq!(remainders.in_order_traversal(), [0, 2, 3, 5, 7, 8]);
+        assert!(remainders.is_valid_bst() && remainders.verify_caches().is_ok());
+        let labels = sample().map(|value| value.to_string());
+        assert_eq!(labels.first().map(String::as_str), Some("10"));
+    }
+
+    #[test]
+    fn symmetric_difference_is_union_minus_intersection() {
+        let left: BinarySearchTree<i32> = [10, 5, 15, 3, 7].into_iter().collect();
+        let right: BinarySearchTree<i32> = [7, 10, 12, 18, 20].into_iter().collect();
+        let pair: BinarySearchTree<i32> = [3, 7].into_iter().collect();
+        let empty = BinarySearchTree::new();
+        assert_eq!(left.symmetric_difference(&right).in_order_traversal(), [3, 5, 12, 15, 18, 20]);
+        for (a, b) in [(&left, &right), (&right, &left), (&left, &pair), (&pair, &empty), (&left, &left)] {
+            assert_eq!(a.symmetric_difference(b), a.union(b).difference(&a.intersection(b)));
+            let ops = a.set_ops(b);
+            assert!(ops.union == a.union(b) && ops.intersection == a.intersection(b));
+            assert!(ops.difference == a.difference(b) && ops.symmetric_difference == a.symmetric_difference(b));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn structural_hash_tells_shapes_apart() {
+        let balanced = sample();
+        let same_shape: BinarySearchTree<i32> = [10, 15, 5, 18, 12, 7, 3].into_iter().collect();
+        let degenerate: BinarySearchTree<i32> = [3, 5, 7, 10, 12, 15, 18].into_iter().collect();
+        assert_eq!(balanced, degenerate);
+        assert!(balanced.structural_eq(&same_shape) && !balanced.structural_eq(&degenerate));
+        assert_eq!(balanced.structural_hash(), same_shape.structural_hash());
+        assert_ne!(balanced.structural_hash(), degenerate.structural_hash());
+        assert_ne!(balanced.structural_hash(), balanced.map(|value| value + 1).structural_hash());
+    }
+
+    #[test]
+    fn presized_arena_keeps_its_capacity() {
+        let mut arena: ArenaBST<u32> = ArenaBST::with_capacity(10_000);
+        let capacity = arena.capacity();
+        assert!(capacity >= 10_000);
+        for value in (0..10_000).map(|i| i * 7919 % 10_000) {
+            arena.insert(value).unwrap();
+            assert_eq!(arena.capacity(), capacity);
+        }
+        arena.reserve(5_000);
+        assert!(arena.capacity() >= 15_000);
+        let mut boxed = BinarySearchTree::<u32>::with_capacity(10);
+        boxed.reserve(10);
+        assert!(boxed.is_empty());
+    }
+
+    #[test]
+    fn try_insert_returns_both_values_on_conflict() {
+        let mut tree = BinarySearchTree::new();
+        assert_eq!(tree.try_insert(10), Ok(&10));
+        assert_eq!(tree.try_insert(10), Err((&10, 10)));
+        let mut records: BinarySearchTree<Record> = [Record { id: 3, payload: "stored" }].into_iter().collect();
+        let (stored, rejected) = records.try_insert(Record { id: 3, payload: "rejected" }).unwrap_err();
+        assert_eq!((stored.payload, rejected.payload), ("stored", "rejected"));
+    }
+
+    #[test]
+    fn min_and_max_by_key_scan_every_value() {
+        let fruits: BinarySearchTree<String> = ["pear", "grape", "fig", "apple", "kiwi"].iter().map(|word| word.to_string()).collect();
+        assert_eq!(fruits.max_by_key(|word| word.len()).map(String::as_str), Some("apple"));
+        assert_eq!(fruits.min_by_key(|word| word.len()).map(String::as_str), Some("fig"));
+        assert_eq!(BinarySearchTree::<String>::new().max_by_key(|word| word.len()), None);
+    }
+
+    #[test]
+    fn prune_keeps_one_side() {
+        let mut tree = sample();
+        tree.prune_below(&10);
+        assert_eq!(tree.in_order_traversal(), [10, 12, 15, 18]);
+        assert!(tree.verify_caches().is_ok() && tree.len() == 4);
+        tree.prune_above(&14);
+        assert_eq!(tree.in_order_traversal(), [10, 12]);
+        assert!(tree.is_valid_bst() && tree.verify_caches().is_ok());
+        tree.prune_below(&100);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn sorted_builds_cost_quadratic_comparisons() {
+        let n = 1023;
+        let (mut sorted_cost, mut balanced_cost) = (0, 0);
+        let mut degenerate = BinarySearchTree::new();
+        for value in 0..n {
+            degenerate.insert_counting(value, &mut sorted_cost).unwrap();
+        }
+        let mut level_built = BinarySearchTree::new();
+        for value in BinarySearchTree::from_sorted_slice(&(0..n).collect::<Vec<_>>()).pre_order_traversal() {
+            level_built.insert_counting(value, &mut balanced_cost).unwrap();
+        }
+        // The k-th sorted insert compares against all k values stored before it
+        assert_eq!(sorted_cost, (n * (n - 1) / 2) as usize);
+        // Building level by level keeps every insert within log2(1024) = 10 comparisons
+        assert!(balanced_cost <= n as usize * 10);
+        let mut duplicate_cost = 0;
+        assert!(level_built.insert_counting(511, &mut duplicate_cost).is_err());
+        assert_eq!(duplicate_cost, 1);
+    }
+
+    #[test]
+    fn is_symmetric_compares_shapes_only() {
+        assert!(sample().is_symmetric());
+        assert!(BinarySearchTree::<i32>::new().is_symmetric());
+        let mut lopsided = sample();
+        lopsided.insert(1).unwrap();
+        assert!(!lopsided.is_symmetric());
+        let inward: BinarySearchTree<i32> = [10, 5, 15, 7, 12].into_iter().collect();
+        assert!(inward.is_symmetric());
+        let skewed: BinarySearchTree<i32> = (0..3).collect();
+        assert!(!skewed.is_symmetric());
+    }
+
+    #[test]
+    fn for_each_mut_keeps_the_order() {
+        let mut records: BinarySearchTree<Record> = (1..=5).map(|id| Record { id, payload: "unvisited" }).collect();
+        records.for_each_mut(|record| record.payload = if record.id % 2 == 0 { "even" } else { "odd" });
+        let payloads: Vec<&str> = records.iter().map(|record| record.payload).collect();
+        assert_eq!(payloads, ["odd", "even", "odd", "even", "odd"]);
+        assert!(records.is_valid_bst());
+        assert_eq!(records.get(&Record { id: 4, payload: "query" }).unwrap().payload, "even");
+    }
+
+    #[test]
+    fn range_bounds_of_an_empty_range_is_none() {
+        let tree = sample();
+        assert_eq!(tree.range_bounds(&6, &13), Some((&7, &12)));
+        assert_eq!(tree.range_bounds(&7, &7), Some((&7, &7)));
+        assert_eq!(tree.range_bounds(&0, &100), Some((&3, &18)));
+        assert_eq!((tree.range_bounds(&8, &9), tree.range_bounds(&13, &6)), (None, None));
+        assert_eq!(tree.range_bounds(&19, &30), None);
+    }
+
+    #[test]
+    fn btree_set_round_trips() {
+        let standard: BTreeSet<i32> = [8, 3, 5, 1, 9].into_iter().collect();
+        let tree = BinarySearchTree::from(standard.clone());
+        assert!(tree.is_balanced());
+        assert_eq!(tree.in_order_traversal(), [1, 3, 5, 8, 9]);
+        assert_eq!(BTreeSet::from(tree), standard);
+    }
+
+    #[test]
+    fn successor_and_predecessor_stop_at_the_ends() {
+        let tree = sample();
+        assert_eq!((tree.successor(&18), tree.predecessor(&3)), (None, None));
+        assert_eq!((tree.successor(&10), tree.predecessor(&10)), (Some(&12), Some(&7)));
+        assert_eq!((tree.successor(&8), tree.predecessor(&8)), (Some(&10), Some(&7)));
+        assert_eq!((tree.successor(&0), tree.predecessor(&0)), (Some(&3), None));
+        assert_eq!((tree.successor(&100), tree.predecessor(&100)), (None, Some(&18)));
+        assert_eq!(BinarySearchTree::<i32>::new().successor(&5), None);
+    }
+
+    #[test]
+    fn failed_inserts_and_deletes_leave_the_length_alone() {
+        let mut tree = sample();
+        assert!(matches!(tree.insert(12), Err(BSTError::DuplicateValue(12))));
+        assert!(matches!(tree.delete(9), Err(BSTError::ValueNotFound(9))));
+        assert_eq!((tree.len(), tree.is_empty()), (7, false));
+        assert_eq!(tree.len(), tree.count_nodes());
+        let mut empty = BinarySearchTree::new();
+        assert!(matches!(empty.delete(1), Err(BSTError::ValueNotFound(1))));
+        assert_eq!((empty.len(), empty.is_empty(), empty.count_nodes()), (0, true, 0));
+        empty.insert(1).unwrap();
+        assert!(matches!(empty.insert(1), Err(BSTError::DuplicateValue(1))));
+        assert_eq!((empty.len(), empty.is_empty(), empty.count_nodes()), (1, false, 1));
+    }
+
+    #[test]
+    fn deep_maps_drop_without_overflowing() {
+        let mut deep = map::vine(300_000);
+        assert_eq!(deep.get(&299_999), Some(&299_999));
+        assert_eq!(deep.remove(&0), Some(0));
+        assert_eq!((deep.len(), deep.iter().count()), (299_999, 299_999));
+        assert_eq!(deep.iter().next(), Some((&1, &1)));
+        drop(deep);
+    }
+}
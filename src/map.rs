@@ -0,0 +1,201 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+// Define the structure of a node in the map, ordered by its key only
+#[derive(Debug)]
+struct MapNode<K: Ord, V> {
+    key: K,
+    value: V,
+    left: Option<Box<MapNode<K, V>>>,
+    right: Option<Box<MapNode<K, V>>>,
+}
+
+// Implement methods for the map node
+impl<K: Ord, V> MapNode<K, V> {
+    // Create a new node
+    fn new(key: K, value: V) -> Self {
+        MapNode {
+            key,
+            value,
+            left: None,
+            right: None,
+        }
+    }
+
+    // Find the link that holds `key`, or the empty link where it would be inserted
+    fn find_link<'a>(mut link: &'a mut Option<Box<MapNode<K, V>>>, key: &K) -> &'a mut Option<Box<MapNode<K, V>>> {
+        loop {
+            let ordering = match link.as_ref() {
+                Some(node) => key.cmp(&node.key),
+                None => return link,
+            };
+            match ordering {
+                Ordering::Less => link = &mut link.as_mut().unwrap().left,
+                Ordering::Greater => link = &mut link.as_mut().unwrap().right,
+                Ordering::Equal => return link,
+            }
+        }
+    }
+
+    // Find the node holding `key`, descending iteratively
+    fn find<'a>(mut current: Option<&'a MapNode<K, V>>, key: &K) -> Option<&'a MapNode<K, V>> {
+        while let Some(node) = current {
+            current = match key.cmp(&node.key) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    // Detach the node with the smallest key below a non-empty link, splicing its right child into its place
+    fn take_min(mut link: &mut Option<Box<MapNode<K, V>>>) -> Box<MapNode<K, V>> {
+        while link.as_ref().unwrap().left.is_some() {
+            link = &mut link.as_mut().unwrap().left;
+        }
+        let mut min = link.take().unwrap();
+        *link = min.right.take();
+        min
+    }
+
+    // Free a detached subtree with an explicit work stack, so a deep, degenerate one can't overflow the call stack
+    fn dismantle(link: Option<Box<MapNode<K, V>>>) {
+        let mut stack: Vec<Box<MapNode<K, V>>> = link.into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+/// An ordered map from keys to values, backed by a binary search tree ordered on the keys
+#[derive(Debug)]
+pub struct BSTMap<K: Ord, V> {
+    root: Option<Box<MapNode<K, V>>>,
+    size: usize,
+}
+
+// Implement methods for the map
+impl<K: Ord, V> BSTMap<K, V> {
+    /// Create a new empty map
+    pub fn new() -> Self {
+        BSTMap { root: None, size: 0 }
+    }
+
+    /// Insert a key-value pair, returning the previous value if the key was already present
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let link = MapNode::find_link(&mut self.root, &key);
+        match link {
+            Some(node) => Some(core::mem::replace(&mut node.value, value)),
+            None => {
+                *link = Some(Box::new(MapNode::new(key, value)));
+                self.size += 1;
+                None
+            }
+        }
+    }
+
+    /// Get a reference to the value stored under `key`
+    pub fn get(&self, key: &K) -> Option<&V> {
+        MapNode::find(self.root.as_deref(), key).map(|node| &node.value)
+    }
+
+    /// Get a mutable reference to the value stored under `key`
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        MapNode::find_link(&mut self.root, key).as_mut().map(|node| &mut node.value)
+    }
+
+    /// Check whether the map holds `key`
+    pub fn contains_key(&self, key: &K) -> bool {
+        MapNode::find(self.root.as_deref(), key).is_some()
+    }
+
+    /// Remove `key` from the map, returning its value if it was present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let link = MapNode::find_link(&mut self.root, key);
+        let mut node = link.take()?;
+        *link = match (node.left.take(), node.right.take()) {
+            (None, right) => right,
+            (left, None) => left,
+            (left, Some(right)) => {
+                // Node has two children, lift the in-order successor (minimum in the right subtree) into its place
+                let mut right = Some(right);
+                let mut successor = MapNode::take_min(&mut right);
+                successor.left = left;
+                successor.right = right;
+                Some(successor)
+            }
+        };
+        self.size -= 1;
+        Some(node.value)
+    }
+
+    /// Number of entries in the map
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Check if the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Iterate over the entries in ascending key order
+    pub fn iter(&self) -> MapIter<'_, K, V> {
+        let mut iter = MapIter { stack: Vec::new() };
+        iter.push_left_spine(self.root.as_deref());
+        iter
+    }
+}
+
+impl<K: Ord, V> Default for BSTMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> Drop for BSTMap<K, V> {
+    fn drop(&mut self) {
+        MapNode::dismantle(self.root.take());
+    }
+}
+
+// Build a map whose keys `0..n` form a vine of right children in O(n) by linking the nodes
+// bottom-up, which inserting them could only do in O(n^2)
+#[cfg(test)]
+pub(crate) fn vine(n: i32) -> BSTMap<i32, i32> {
+    let mut root = None;
+    for key in (0..n).rev() {
+        let mut node = Box::new(MapNode::new(key, key));
+        node.right = root;
+        root = Some(node);
+    }
+    BSTMap { root, size: n as usize }
+}
+
+/// Lazy in-order iterator over a `BSTMap`, yielding entries in ascending key order
+pub struct MapIter<'a, K: Ord, V> {
+    stack: Vec<&'a MapNode<K, V>>,
+}
+
+impl<'a, K: Ord, V> MapIter<'a, K, V> {
+    // Push a node and its chain of left descendants, so the smallest pending key is on top
+    fn push_left_spine(&mut self, mut node: Option<&'a MapNode<K, V>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = current.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for MapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
@@ -0,0 +1,84 @@
+use crate::BSTMap;
+use alloc::vec::Vec;
+
+/// An ordered multiset that keeps a count for every value instead of rejecting duplicates
+#[derive(Debug)]
+pub struct BSTMultiset<T: Ord> {
+    counts: BSTMap<T, usize>,
+    size: usize,
+}
+
+// Implement methods for the multiset
+impl<T: Ord> BSTMultiset<T> {
+    /// Create a new empty multiset
+    pub fn new() -> Self {
+        BSTMultiset {
+            counts: BSTMap::new(),
+            size: 0,
+        }
+    }
+
+    /// Insert a value, incrementing its count if it's already present
+    pub fn insert(&mut self, value: T) {
+        match self.counts.get_mut(&value) {
+            Some(count) => *count += 1,
+            None => {
+                self.counts.insert(value, 1);
+            }
+        }
+        self.size += 1;
+    }
+
+    /// Remove one occurrence of a value, returning whether it was present.
+    /// The value is pruned from the tree once its count drops to zero.
+    pub fn remove_one(&mut self, value: &T) -> bool {
+        match self.counts.get_mut(value) {
+            Some(count) if *count > 1 => *count -= 1,
+            Some(_) => {
+                self.counts.remove(value);
+            }
+            None => return false,
+        }
+        self.size -= 1;
+        true
+    }
+
+    /// Number of times a value was inserted (and not yet removed)
+    pub fn count_of(&self, value: &T) -> usize {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    /// Total number of values, counting every occurrence
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Number of distinct values
+    pub fn distinct_len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Check if the multiset holds no values
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Iterate in ascending order, yielding each value as many times as it occurs
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.counts.iter().flat_map(|(value, &count)| core::iter::repeat_n(value, count))
+    }
+
+    /// In-order traversal, repeating each value as many times as it occurs
+    pub fn in_order_traversal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: Ord> Default for BSTMultiset<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,218 @@
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+// A node of the persistent tree. Nodes are never modified once built, so several versions of
+// the tree can point at the same one.
+#[derive(Debug)]
+struct PersistentNode<T> {
+    value: T,
+    left: Option<Rc<PersistentNode<T>>>,
+    right: Option<Rc<PersistentNode<T>>>,
+}
+
+/// An immutable binary search tree where `insert` and `delete` return a new version and leave
+/// the old one intact. Only the nodes along the modified path are copied; every other subtree is
+/// shared between the versions, so each update allocates O(height) nodes.
+#[derive(Debug, Clone)]
+pub struct PersistentBST<T: Ord + Clone> {
+    root: Option<Rc<PersistentNode<T>>>,
+    size: usize,
+}
+
+// Implement methods for the persistent tree
+impl<T: Ord + Clone> PersistentBST<T> {
+    /// Create a new empty persistent tree
+    pub fn new() -> Self {
+        PersistentBST { root: None, size: 0 }
+    }
+
+    // Collect the nodes from the root down towards `value`, with the direction taken at each,
+    // and the node holding `value` if there is one
+    #[allow(clippy::type_complexity)]
+    fn path_to(&self, value: &T) -> (Vec<(&Rc<PersistentNode<T>>, Ordering)>, Option<&Rc<PersistentNode<T>>>) {
+        let mut path = Vec::new();
+        let mut current = self.root.as_ref();
+        while let Some(node) = current {
+            let ordering = value.cmp(&node.value);
+            if ordering == Ordering::Equal {
+                return (path, Some(node));
+            }
+            current = match ordering {
+                Ordering::Less => node.left.as_ref(),
+                _ => node.right.as_ref(),
+            };
+            path.push((node, ordering));
+        }
+        (path, None)
+    }
+
+    // Copy the nodes along a path bottom-up, linking each copy to the new subtree below it and
+    // sharing the child on the other side
+    fn rebuild_path(path: &[(&Rc<PersistentNode<T>>, Ordering)], mut subtree: Option<Rc<PersistentNode<T>>>) -> Option<Rc<PersistentNode<T>>> {
+        for &(node, ordering) in path.iter().rev() {
+            let (left, right) = match ordering {
+                Ordering::Less => (subtree, node.right.clone()),
+                _ => (node.left.clone(), subtree),
+            };
+            subtree = Some(Rc::new(PersistentNode {
+                value: node.value.clone(),
+                left,
+                right,
+            }));
+        }
+        subtree
+    }
+
+    /// Return a new version with `value` inserted, sharing every subtree off the insertion path.
+    /// If `value` is already present the new version is the same tree.
+    pub fn insert(&self, value: T) -> Self {
+        let (path, found) = self.path_to(&value);
+        if found.is_some() {
+            return self.clone();
+        }
+        let leaf = Some(Rc::new(PersistentNode {
+            value,
+            left: None,
+            right: None,
+        }));
+        PersistentBST {
+            root: Self::rebuild_path(&path, leaf),
+            size: self.size + 1,
+        }
+    }
+
+    /// Return a new version without `value`, sharing every subtree off the modified path.
+    /// If `value` isn't present the new version is the same tree.
+    pub fn delete(&self, value: &T) -> Self {
+        let (path, Some(node)) = self.path_to(value) else {
+            return self.clone();
+        };
+        let replacement = match (&node.left, &node.right) {
+            (None, child) | (child, None) => child.clone(),
+            (Some(left), Some(right)) => {
+                // Node has two children, copy the in-order successor (minimum in the right subtree) into its place
+                let mut spine = Vec::new();
+                let mut successor = right;
+                while let Some(next) = successor.left.as_ref() {
+                    spine.push((successor, Ordering::Less));
+                    successor = next;
+                }
+                Some(Rc::new(PersistentNode {
+                    value: successor.value.clone(),
+                    left: Some(Rc::clone(left)),
+                    right: Self::rebuild_path(&spine, successor.right.clone()),
+                }))
+            }
+        };
+        PersistentBST {
+            root: Self::rebuild_path(&path, replacement),
+            size: self.size - 1,
+        }
+    }
+
+    /// Check whether a value is in this version
+    pub fn contains(&self, value: &T) -> bool {
+        self.path_to(value).1.is_some()
+    }
+
+    /// Get a reference to the stored value equal to `value`
+    pub fn get(&self, value: &T) -> Option<&T> {
+        self.path_to(value).1.map(|node| &node.value)
+    }
+
+    /// Iterate over the values of this version in sorted order
+    pub fn iter(&self) -> PersistentIter<'_, T> {
+        let mut iter = PersistentIter { stack: Vec::new() };
+        iter.push_left_spine(self.root.as_deref());
+        iter
+    }
+
+    /// In-order traversal
+    pub fn in_order_traversal(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+
+    /// Number of values in this version, in O(1)
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Check if this version holds no values, in O(1)
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Count the nodes of this version that are physically shared with `other`, i.e. the same
+    /// allocation rather than an equal copy. Useful to check how much two versions share.
+    pub fn shared_nodes(&self, other: &Self) -> usize {
+        let mut theirs: Vec<*const PersistentNode<T>> = Vec::with_capacity(other.size);
+        let mut stack: Vec<&Rc<PersistentNode<T>>> = other.root.iter().collect();
+        while let Some(node) = stack.pop() {
+            theirs.push(Rc::as_ptr(node));
+            stack.extend(node.left.iter().chain(node.right.iter()));
+        }
+        theirs.sort_unstable();
+        let mut shared = 0;
+        let mut stack: Vec<&Rc<PersistentNode<T>>> = self.root.iter().collect();
+        while let Some(node) = stack.pop() {
+            if theirs.binary_search(&Rc::as_ptr(node)).is_ok() {
+                shared += 1;
+            }
+            stack.extend(node.left.iter().chain(node.right.iter()));
+        }
+        shared
+    }
+}
+
+impl<T: Ord + Clone> Default for PersistentBST<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Duplicate values are skipped, just as `insert` would ignore them
+impl<T: Ord + Clone> FromIterator<T> for PersistentBST<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().fold(PersistentBST::new(), |tree, value| tree.insert(value))
+    }
+}
+
+// Release the nodes only this version owns without recursing once per level; nodes still
+// shared with another version just lose a reference
+impl<T: Ord + Clone> Drop for PersistentBST<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<PersistentNode<T>>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+/// Lazy in-order iterator over a version of a `PersistentBST`
+pub struct PersistentIter<'a, T> {
+    stack: Vec<&'a PersistentNode<T>>,
+}
+
+impl<'a, T> PersistentIter<'a, T> {
+    // Push a node and its chain of left descendants, so the smallest pending value is on top
+    fn push_left_spine(&mut self, mut node: Option<&'a PersistentNode<T>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = current.left.as_deref();
+        }
+    }
+}
+
+impl<'a, T> Iterator for PersistentIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right.as_deref());
+        Some(&node.value)
+    }
+}
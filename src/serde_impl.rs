@@ -0,0 +1,19 @@
+use crate::{BinarySearchTree, Comparator};
+use alloc::vec::Vec;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+// Serialize the BST as its values in sorted order
+impl<T: Serialize, C: Comparator<T>> Serialize for BinarySearchTree<T, C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+// Deserialize a sequence of values into a balanced BST. The values are sorted and de-duplicated
+// before building, so a sequence that didn't come from `Serialize` still yields a valid BST.
+impl<'de, T: Ord + Deserialize<'de>> Deserialize<'de> for BinarySearchTree<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(BinarySearchTree::from)
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{BinarySearchTree, Comparator, InOrderIter, Natural};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// A read-only handle to a BST that can be cloned cheaply and shared across threads.
+/// Cloning only bumps a reference count, and the handle has no methods that take `&mut self`,
+/// so the tree can't change once shared. The handle is `Send` and `Sync` whenever `T` and the
+/// comparator are both `Send` and `Sync`.
+#[derive(Debug)]
+pub struct SharedBST<T, C = Natural> {
+    tree: Arc<BinarySearchTree<T, C>>,
+}
+
+// Implement the read-only methods, deferring to the shared tree
+impl<T, C: Comparator<T>> SharedBST<T, C> {
+    pub(crate) fn new(tree: BinarySearchTree<T, C>) -> Self {
+        SharedBST { tree: Arc::new(tree) }
+    }
+
+    /// Search for a value in the shared BST
+    pub fn search(&self, value: T) -> bool {
+        self.tree.contains(&value)
+    }
+
+    /// Check whether a value is in the shared BST without taking ownership of the query
+    pub fn contains(&self, value: &T) -> bool {
+        self.tree.contains(value)
+    }
+
+    /// Get a reference to the stored value equal to `value`
+    pub fn get(&self, value: &T) -> Option<&T> {
+        self.tree.get(value)
+    }
+
+    /// Iterate over the values in sorted order
+    pub fn iter(&self) -> InOrderIter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Values from `low` to `high` inclusive in sorted order; an inverted range (`low > high`) is empty
+    pub fn range(&self, low: &T, high: &T) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.tree.range(low, high)
+    }
+
+    /// Number of values in the shared BST, in O(1)
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Check if the shared BST holds no values, in O(1)
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+}
+
+// Cloning shares the same tree rather than copying it, so no `T: Clone` bound is needed
+impl<T, C> Clone for SharedBST<T, C> {
+    fn clone(&self) -> Self {
+        SharedBST {
+            tree: Arc::clone(&self.tree),
+        }
+    }
+}
+
+// Fail the build if the handle ever stops being shareable across threads for thread-safe values
+const _: fn() = || {
+    fn assert_send_sync<S: Send + Sync>() {}
+    assert_send_sync::<SharedBST<u32>>();
+};